@@ -0,0 +1,322 @@
+use std::collections::HashSet;
+use serde_json::Value;
+use tauri::async_runtime::spawn_blocking;
+use crate::state::{AppState, MainDocument};
+use crate::document::JsonNode;
+use crate::schema::infer_with_integer_widths;
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "abstract", "become", "box", "do", "final", "gen", "macro", "override", "priv", "try",
+    "typeof", "unsized", "virtual", "yield",
+];
+
+// Type names a generated struct/interface must not take: they would shadow prelude or imported
+// types that the generated code itself refers to
+const RUST_RESERVED_TYPES: &[&str] = &[
+    "Self", "String", "Option", "Some", "None", "Vec", "Box", "Result", "Ok", "Err",
+    "Serialize", "Deserialize",
+];
+const TYPESCRIPT_RESERVED_TYPES: &[&str] = &[
+    "String", "Number", "Boolean", "Object", "Array", "Date", "Function", "Symbol", "Promise",
+    "Record", "Partial", "Map", "Set",
+];
+
+// Split a JSON key into lowercase words at separators and camelCase boundaries
+fn words(key: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+    for c in key.chars() {
+        if !c.is_ascii_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_ascii_uppercase() && prev_lower && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        current.push(c.to_ascii_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn pascal_case(key: &str) -> String {
+    let name: String = words(key)
+        .iter()
+        .map(|w| w[..1].to_ascii_uppercase() + &w[1..])
+        .collect();
+    match name.chars().next() {
+        None => "Value".into(),
+        Some(c) if c.is_ascii_digit() => format!("T{name}"),
+        _ => name,
+    }
+}
+
+fn snake_case(key: &str) -> String {
+    let name = words(key).join("_");
+    match name.chars().next() {
+        None => "field".into(),
+        Some(c) if c.is_ascii_digit() => format!("_{name}"),
+        // These can't be raw identifiers
+        _ if matches!(name.as_str(), "crate" | "self" | "super") => format!("{name}_"),
+        _ if RUST_KEYWORDS.contains(&name.as_str()) => format!("r#{name}"),
+        _ => name,
+    }
+}
+
+// Type name for the elements of an array stored under `key` ("users" -> "User")
+fn singular(name: &str) -> String {
+    let sibilant = |stem: &&str| ["ss", "x", "ch", "sh"].iter().any(|end| stem.ends_with(end));
+    if let Some(stem) = name.strip_suffix("ies") {
+        format!("{stem}y")
+    } else if let Some(stem) = name.strip_suffix("es").filter(sibilant) {
+        stem.to_string()
+    } else if name.len() > 1 && name.ends_with('s') && !name.ends_with("ss") && !name.ends_with("us") {
+        name[..name.len() - 1].to_string()
+    } else {
+        format!("{name}Item")
+    }
+}
+
+fn schema_types(schema: &Value) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(ts)) => ts.iter().filter_map(Value::as_str).collect(),
+        _ => vec![],
+    }
+}
+
+// Emits type definitions for an inferred schema. Definitions are listed parent first; nested
+// objects get a struct/interface named after their key, made unique when names repeat.
+struct Generator {
+    typescript: bool,
+    names: HashSet<String>,
+    defs: Vec<String>,
+}
+
+impl Generator {
+    fn new(typescript: bool) -> Self {
+        let reserved = if typescript { TYPESCRIPT_RESERVED_TYPES } else { RUST_RESERVED_TYPES };
+        let names = reserved.iter().map(|name| name.to_string()).collect();
+        Generator { typescript, names, defs: Vec::new() }
+    }
+
+    fn unique_name(&mut self, base: &str) -> String {
+        let mut name = base.to_string();
+        let mut n = 2;
+        while !self.names.insert(name.clone()) {
+            name = format!("{base}{n}");
+            n += 1;
+        }
+        name
+    }
+
+    // Type expression for `schema`, defining nested objects as `name` along the way
+    fn type_of(&mut self, schema: &Value, name: &str) -> String {
+        let types = schema_types(schema);
+        let nullable = types.contains(&"null");
+        let non_null: Vec<&str> = types.iter().copied().filter(|t| *t != "null").collect();
+        let base = match non_null.as_slice() {
+            [] if nullable => return if self.typescript { "null".into() } else { "Option<serde_json::Value>".into() },
+            [single] => self.single_type(schema, single, name),
+            _ if self.typescript && !non_null.is_empty() => non_null.iter()
+                .map(|t| self.single_type(schema, t, name))
+                .collect::<Vec<_>>()
+                .join(" | "),
+            _ => return if self.typescript { "unknown".into() } else { "serde_json::Value".into() },
+        };
+        match (nullable, self.typescript) {
+            (false, _) => base,
+            (true, true) => format!("{base} | null"),
+            (true, false) => format!("Option<{base}>"),
+        }
+    }
+
+    fn single_type(&mut self, schema: &Value, ty: &str, name: &str) -> String {
+        match (ty, self.typescript) {
+            ("object", _) => self.define_object(schema, name),
+            ("array", _) => {
+                let item = match schema.get("items") {
+                    Some(items) => self.type_of(items, &singular(name)),
+                    None if self.typescript => "unknown".into(),
+                    None => "serde_json::Value".into(),
+                };
+                if !self.typescript {
+                    format!("Vec<{item}>")
+                } else if item.contains(' ') {
+                    format!("({item})[]")
+                } else {
+                    format!("{item}[]")
+                }
+            }
+            ("string", true) => match schema.get("enum").and_then(Value::as_array) {
+                Some(options) => options.iter()
+                    .filter(|v| v.is_string())
+                    .map(Value::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" | "),
+                None => "string".into(),
+            },
+            ("string", false) => "String".into(),
+            ("integer" | "number", true) => "number".into(),
+            // Integers beyond 64 bits keep their exact value in a Number
+            ("integer", false) => match schema.get("format").and_then(Value::as_str) {
+                Some("int64") => "i64".into(),
+                Some("uint64") => "u64".into(),
+                _ => "serde_json::Number".into(),
+            },
+            ("number", false) => "f64".into(),
+            ("boolean", true) => "boolean".into(),
+            ("boolean", false) => "bool".into(),
+            (_, true) => "unknown".into(),
+            (_, false) => "serde_json::Value".into(),
+        }
+    }
+
+    fn define_object(&mut self, schema: &Value, name: &str) -> String {
+        let type_name = self.unique_name(&pascal_case(name));
+        let slot = self.defs.len();
+        self.defs.push(String::new()); // parent goes before the types its fields define
+
+        let required: HashSet<&str> = schema.get("required")
+            .and_then(Value::as_array)
+            .map(|r| r.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        let empty = serde_json::Map::new();
+        let properties = schema.get("properties").and_then(Value::as_object).unwrap_or(&empty);
+
+        let mut body = String::new();
+        let mut fields = HashSet::new();
+        for (key, property) in properties {
+            let ty = self.type_of(property, key);
+            let optional = !required.contains(key.as_str());
+            if self.typescript {
+                let valid_ident = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '$')
+                    && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+                let prop = if valid_ident { key.clone() } else { Value::String(key.clone()).to_string() };
+                body.push_str(&format!("  {prop}{}: {ty};\n", if optional { "?" } else { "" }));
+            } else {
+                let mut field = snake_case(key);
+                let mut n = 2;
+                while !fields.insert(field.clone()) {
+                    field = format!("{}_{n}", snake_case(key));
+                    n += 1;
+                }
+                if field.trim_start_matches("r#") != key {
+                    body.push_str(&format!("    #[serde(rename = {})]\n", Value::String(key.clone())));
+                }
+                // Missing keys deserialize to None
+                let ty = if optional && !ty.starts_with("Option<") { format!("Option<{ty}>") } else { ty };
+                body.push_str(&format!("    pub {field}: {ty},\n"));
+            }
+        }
+
+        self.defs[slot] = if self.typescript {
+            format!("export interface {type_name} {{\n{body}}}\n")
+        } else {
+            format!("#[derive(Debug, Clone, Serialize, Deserialize)]\npub struct {type_name} {{\n{body}}}\n")
+        };
+        type_name
+    }
+}
+
+// Generate type definitions for the value at `pointer` (default: root), inferring one shape across
+// all array samples. `language` is "rust" (serde structs) or "typescript" (interfaces).
+// Returns the code, and also copies it to the clipboard when `copy` is set.
+#[tauri::command]
+pub async fn generate_types(pointer: Option<String>, language: String, root_name: Option<String>, copy: Option<bool>, state: tauri::State<'_, AppState>) -> Result<String, String> {
+    let doc = state.main_document()?;
+    let pointer = pointer.unwrap_or_default();
+    let typescript = match language.as_str() {
+        "rust" => false,
+        "typescript" | "ts" => true,
+        other => return Err(format!("Unsupported language: {other}")),
+    };
+    let code = spawn_blocking(move || -> Result<String, String> {
+        let schema = match &doc {
+            MainDocument::Tree(doc) => doc.pointer(&pointer).map(infer_with_integer_widths),
+            MainDocument::Compact(doc) => doc.root().pointer(&pointer).map(infer_with_integer_widths),
+        }
+        .ok_or("Invalid pointer")?;
+        let mut generator = Generator::new(typescript);
+        let root = root_name.unwrap_or_else(|| "Root".into());
+        let root_type = generator.type_of(&schema, &root);
+        // A root that isn't an object gets an alias so there is always a type named `root`
+        if generator.names.insert(pascal_case(&root)) {
+            let alias = if typescript {
+                format!("export type {} = {root_type};\n", pascal_case(&root))
+            } else {
+                format!("pub type {} = {root_type};\n", pascal_case(&root))
+            };
+            generator.defs.insert(0, alias);
+        }
+        let header = if typescript { "" } else { "use serde::{Deserialize, Serialize};\n\n" };
+        Ok(format!("{header}{}", generator.defs.join("\n")))
+    })
+    .await
+    .map_err(|e| format!("Join error: {e}"))??;
+
+    if copy.unwrap_or(false) {
+        use arboard::Clipboard;
+        let mut cb = Clipboard::new().map_err(|e| e.to_string())?;
+        cb.set_text(code.clone()).map_err(|e| e.to_string())?;
+    }
+    Ok(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn types(json: &str, typescript: bool) -> String {
+        let value: Value = serde_json::from_str(json).unwrap();
+        let mut generator = Generator::new(typescript);
+        generator.type_of(&infer_with_integer_widths(&value), "Root");
+        generator.defs.join("\n")
+    }
+
+    fn rust_types(json: &str) -> String {
+        types(json, false)
+    }
+
+    #[test]
+    fn integer_fields_fit_every_sample() {
+        let code = rust_types(r#"[
+            {"small": -5, "id": 12345678901234567890, "huge": 123456789012345678901234567890},
+            {"small": 7, "id": 1, "huge": 2}
+        ]"#);
+        assert!(code.contains("pub small: i64,"), "{code}");
+        assert!(code.contains("pub id: u64,"), "{code}");
+        assert!(code.contains("pub huge: serde_json::Number,"), "{code}");
+    }
+
+    #[test]
+    fn type_names_avoid_prelude_and_imported_types() {
+        let code = rust_types(r#"{"string": {"a": 1}, "option": {"b": 2}, "self": {"c": 3}, "serialize": {"d": 4}}"#);
+        assert!(code.contains("pub struct String2 {"), "{code}");
+        assert!(code.contains("pub string: String2,"), "{code}");
+        assert!(code.contains("pub struct Option2 {"), "{code}");
+        assert!(code.contains("pub struct Self2 {"), "{code}");
+        assert!(code.contains("pub struct Serialize2 {"), "{code}");
+
+        let code = types(r#"{"date": {"iso": "x"}, "record": {"id": 1}}"#, true);
+        assert!(code.contains("export interface Date2 {"), "{code}");
+        assert!(code.contains("export interface Record2 {"), "{code}");
+    }
+
+    #[test]
+    fn inferred_schema_has_no_integer_widths() {
+        let value: Value = serde_json::from_str(r#"{"id": 1}"#).unwrap();
+        assert_eq!(crate::schema::infer(&value)["properties"]["id"], serde_json::json!({"type": "integer"}));
+        assert_eq!(infer_with_integer_widths(&value)["properties"]["id"]["format"], "int64");
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use serde_json::Value;
+use tauri::async_runtime::spawn_blocking;
+use crate::state::AppState;
+use crate::types::{DiffEntry, DiffResponse, DiffSummary, Node, OpenError};
+use crate::tree::{build_node_for_pointer, child_pointer, list_children, mark_with_ancestors};
+use crate::file::{is_ndjson, read_document};
+use crate::patch::json_equal;
+
+#[derive(Clone, Copy, PartialEq)]
+enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl ChangeKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Changed => "changed",
+        }
+    }
+}
+
+struct Change {
+    kind: ChangeKind,
+    left: Option<String>,
+    right: Option<String>,
+}
+
+// Structural diff of the main document (left) against the compare document (right).
+// Besides the flat change list it keeps a status per pointer on each side, so tree nodes can be
+// annotated: the changed node itself, plus "modified" on every ancestor.
+pub struct Diff {
+    changes: Vec<Change>,
+    left_status: HashMap<String, &'static str>,
+    right_status: HashMap<String, &'static str>,
+}
+
+impl Diff {
+    // `array_key`: match array elements by this member (e.g. "id") instead of by index
+    fn compute(left: &Value, right: &Value, array_key: Option<&str>) -> Diff {
+        let mut differ = Differ { array_key, changes: Vec::new() };
+        differ.diff(left, right, "", "");
+
+        let mut left_status = HashMap::new();
+        let mut right_status = HashMap::new();
+        for change in &differ.changes {
+            if let Some(p) = &change.left {
+                mark_with_ancestors(&mut left_status, p, change.kind.as_str(), "modified");
+            }
+            if let Some(p) = &change.right {
+                mark_with_ancestors(&mut right_status, p, change.kind.as_str(), "modified");
+            }
+        }
+        Diff { changes: differ.changes, left_status, right_status }
+    }
+
+    // Set the diff status of nodes listed from `source` ("main" or "compare")
+    pub fn annotate(&self, source: Option<&str>, nodes: &mut [Node]) {
+        let status = match source.unwrap_or("main") {
+            "main" => &self.left_status,
+            "compare" => &self.right_status,
+            _ => return,
+        };
+        for node in nodes {
+            node.diff = status.get(&node.pointer).map(|s| s.to_string());
+        }
+    }
+}
+
+struct Differ<'a> {
+    array_key: Option<&'a str>,
+    changes: Vec<Change>,
+}
+
+impl Differ<'_> {
+    fn push(&mut self, kind: ChangeKind, left: Option<String>, right: Option<String>) {
+        self.changes.push(Change { kind, left, right });
+    }
+
+    fn diff(&mut self, left: &Value, right: &Value, lp: &str, rp: &str) {
+        match (left, right) {
+            (Value::Object(lm), Value::Object(rm)) => {
+                for (key, lv) in lm {
+                    let lcp = child_pointer(lp, key);
+                    match rm.get(key) {
+                        Some(rv) => self.diff(lv, rv, &lcp, &child_pointer(rp, key)),
+                        None => self.push(ChangeKind::Removed, Some(lcp), None),
+                    }
+                }
+                for key in rm.keys().filter(|k| !lm.contains_key(*k)) {
+                    self.push(ChangeKind::Added, None, Some(child_pointer(rp, key)));
+                }
+            }
+            (Value::Array(la), Value::Array(ra)) => match self.array_key {
+                Some(key) => self.diff_keyed(la, ra, key, lp, rp),
+                None => {
+                    for (i, lv) in la.iter().enumerate() {
+                        let lcp = child_pointer(lp, &i.to_string());
+                        match ra.get(i) {
+                            Some(rv) => self.diff(lv, rv, &lcp, &child_pointer(rp, &i.to_string())),
+                            None => self.push(ChangeKind::Removed, Some(lcp), None),
+                        }
+                    }
+                    for j in la.len()..ra.len() {
+                        self.push(ChangeKind::Added, None, Some(child_pointer(rp, &j.to_string())));
+                    }
+                }
+            },
+            _ => {
+                if !json_equal(left, right) {
+                    self.push(ChangeKind::Changed, Some(lp.to_string()), Some(rp.to_string()));
+                }
+            }
+        }
+    }
+
+    // Pair elements with equal `key` values (in order, for repeated values). Elements without
+    // the key fall back to index matching against a keyless element at the same index.
+    fn diff_keyed(&mut self, la: &[Value], ra: &[Value], key: &str, lp: &str, rp: &str) {
+        let identity = |v: &Value| v.as_object().and_then(|m| m.get(key)).map(Value::to_string);
+        let mut right_by_id: HashMap<String, VecDeque<usize>> = HashMap::new();
+        for (j, rv) in ra.iter().enumerate() {
+            if let Some(id) = identity(rv) {
+                right_by_id.entry(id).or_default().push_back(j);
+            }
+        }
+
+        let mut matched = vec![false; ra.len()];
+        for (i, lv) in la.iter().enumerate() {
+            let partner = match identity(lv) {
+                Some(id) => right_by_id.get_mut(&id).and_then(VecDeque::pop_front),
+                None => ra.get(i).filter(|rv| identity(rv).is_none()).map(|_| i),
+            };
+            let lcp = child_pointer(lp, &i.to_string());
+            match partner {
+                Some(j) => {
+                    matched[j] = true;
+                    self.diff(lv, &ra[j], &lcp, &child_pointer(rp, &j.to_string()));
+                }
+                None => self.push(ChangeKind::Removed, Some(lcp), None),
+            }
+        }
+        for (j, _) in matched.iter().enumerate().filter(|(_, m)| !**m) {
+            self.push(ChangeKind::Added, None, Some(child_pointer(rp, &j.to_string())));
+        }
+    }
+}
+
+// Load a second document to diff against the main one. Returns its top-level nodes (browse it
+// with load_children source "compare"). `lenient` behaves as in open_file. Duplicate keys are
+// not scanned for, since nothing reports them for the compare document.
+#[tauri::command]
+pub async fn open_compare_file(path: String, lenient: Option<bool>, state: tauri::State<'_, AppState>, app_handle: tauri::AppHandle) -> Result<Vec<Node>, OpenError> {
+    let cancel_flag = state.cancel_parse.clone();
+    cancel_flag.store(false, std::sync::atomic::Ordering::SeqCst);
+    let (root, _info) = spawn_blocking(move || read_document(path.clone(), is_ndjson(&path), lenient, false, false, cancel_flag, app_handle))
+        .await
+        .map_err(|e| format!("Join error: {e}"))??;
+
+    let arc = Arc::new(root);
+    let top = list_children(arc.as_ref(), "", 0, 100);
+    *state.compare_doc.write() = Some(arc);
+    *state.diff.write() = None;
+    Ok(top)
+}
+
+#[tauri::command]
+pub fn close_compare(state: tauri::State<'_, AppState>) {
+    *state.compare_doc.write() = None;
+    *state.diff.write() = None;
+}
+
+// Diff the main document against the compare document. Arrays are compared index by index,
+// or by matching elements on `array_key` (e.g. "id") when given. The result is kept for
+// get_diff paging and node annotation until either document changes.
+#[tauri::command]
+pub async fn compute_diff(array_key: Option<String>, state: tauri::State<'_, AppState>) -> Result<DiffSummary, String> {
+    let left = state.document(Some("main"))?;
+    let right = state.document(Some("compare")).map_err(|_| "No compare document loaded".to_string())?;
+    let (left, diff) = spawn_blocking(move || {
+        let diff = Diff::compute(&left, &right, array_key.as_deref());
+        (left, diff)
+    })
+    .await
+    .map_err(|e| format!("Join error: {e}"))?;
+
+    let count = |kind: ChangeKind| diff.changes.iter().filter(|c| c.kind == kind).count();
+    let summary = DiffSummary {
+        added: count(ChangeKind::Added),
+        removed: count(ChangeKind::Removed),
+        changed: count(ChangeKind::Changed),
+    };
+    // An edit that landed while diffing would make the result stale
+    let current = state.doc.read().as_ref().is_some_and(|d| Arc::ptr_eq(d, &left));
+    if !current {
+        return Err("Document changed while diffing; please retry".into());
+    }
+    *state.diff.write() = Some(Arc::new(diff));
+    Ok(summary)
+}
+
+// Page through the changes of the last computed diff, in document order
+#[tauri::command]
+pub fn get_diff(offset: usize, limit: usize, state: tauri::State<'_, AppState>) -> Result<DiffResponse, String> {
+    let diff = state.diff.read().clone().ok_or("No diff computed")?;
+    let left = state.document(Some("main"))?;
+    let right = state.document(Some("compare"))?;
+    let entries = diff.changes
+        .iter()
+        .skip(offset)
+        .take(limit)
+        .map(|c| DiffEntry {
+            kind: c.kind.as_str().to_string(),
+            left: c.left.as_deref().and_then(|p| build_node_for_pointer(left.as_ref(), p).ok()),
+            right: c.right.as_deref().and_then(|p| build_node_for_pointer(right.as_ref(), p).ok()),
+            left_pointer: c.left.clone(),
+            right_pointer: c.right.clone(),
+        })
+        .collect();
+    let total_count = diff.changes.len();
+    Ok(DiffResponse { entries, total_count, has_more: offset + limit < total_count })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changes(left: &str, right: &str) -> Vec<(&'static str, Option<String>)> {
+        let left: Value = serde_json::from_str(left).unwrap();
+        let right: Value = serde_json::from_str(right).unwrap();
+        Diff::compute(&left, &right, None).changes.into_iter().map(|c| (c.kind.as_str(), c.left)).collect()
+    }
+
+    #[test]
+    fn numbers_differing_only_in_text_are_unchanged() {
+        assert!(changes(r#"{"a": 1, "b": [1.50, 2e3, -0]}"#, r#"{"a": 1.0, "b": [1.5, 2000, 0]}"#).is_empty());
+        assert_eq!(changes(r#"{"a": 1, "b": 2}"#, r#"{"a": 1.0, "b": 2.01}"#), [("changed", Some("/b".to_string()))]);
+        assert_eq!(changes("[1]", r#"["1"]"#), [("changed", Some("/0".to_string()))]);
+    }
+}
@@ -0,0 +1,154 @@
+use std::borrow::Cow;
+use serde::Serialize;
+use serde_json::{Number, Value};
+use crate::tree::unescape_pointer_token;
+
+// What a JSON value is, as seen through JsonNode. Containers carry their child count.
+pub enum JsonKind<'a> {
+    Null,
+    Bool(bool),
+    Number(Cow<'a, Number>),
+    String(&'a str),
+    Array(usize),
+    Object(usize),
+}
+
+// A child of a container: its pointer token (object key or array index) and the child itself
+pub type JsonChild<'a, N> = (Cow<'a, str>, N);
+
+// Read-only view of a value in a loaded document. Implemented by `&Value` (the regular tree)
+// and by `TapeRef` (the compact document in tape.rs); tree.rs, search.rs and node.rs browse and
+// search through this trait so they work on either representation.
+pub trait JsonNode<'a>: Copy + Send + Sync + Serialize + 'a {
+    fn kind(self) -> JsonKind<'a>;
+
+    // Children in document order; empty for primitives
+    fn children(self) -> Box<dyn Iterator<Item = JsonChild<'a, Self>> + 'a>;
+
+    // Child by pointer token: object key, or array index in JSON Pointer syntax
+    fn get(self, token: &str) -> Option<Self>;
+
+    fn to_value(self) -> Value;
+
+    // Resolve a JSON Pointer relative to this node ("" is the node itself)
+    fn pointer(self, pointer: &str) -> Option<Self> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        pointer
+            .strip_prefix('/')?
+            .split('/')
+            .try_fold(self, |node, raw| node.get(&unescape_pointer_token(raw)))
+    }
+
+    fn child_count(self) -> usize {
+        match self.kind() {
+            JsonKind::Array(n) | JsonKind::Object(n) => n,
+            _ => 0,
+        }
+    }
+
+    fn is_container(self) -> bool {
+        matches!(self.kind(), JsonKind::Array(_) | JsonKind::Object(_))
+    }
+
+    fn is_array(self) -> bool {
+        matches!(self.kind(), JsonKind::Array(_))
+    }
+
+    fn type_name(self) -> &'static str {
+        match self.kind() {
+            JsonKind::Object(_) => "object",
+            JsonKind::Array(_) => "array",
+            JsonKind::String(_) => "string",
+            JsonKind::Number(_) => "number",
+            JsonKind::Bool(_) => "boolean",
+            JsonKind::Null => "null",
+        }
+    }
+
+    // Text of a string, number or boolean as previews and value search see it
+    fn scalar_text(self) -> Option<Cow<'a, str>> {
+        match self.kind() {
+            JsonKind::String(s) => Some(Cow::Borrowed(s)),
+            JsonKind::Number(n) => Some(Cow::Owned(n.to_string())),
+            JsonKind::Bool(b) => Some(Cow::Owned(b.to_string())),
+            _ => None,
+        }
+    }
+}
+
+// Array index token as JSON Pointer defines it: digits only, no leading zeros
+pub fn parse_index(token: &str) -> Option<usize> {
+    if token.starts_with('+') || (token.starts_with('0') && token.len() != 1) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+impl<'a> JsonNode<'a> for &'a Value {
+    fn kind(self) -> JsonKind<'a> {
+        match self {
+            Value::Null => JsonKind::Null,
+            Value::Bool(b) => JsonKind::Bool(*b),
+            Value::Number(n) => JsonKind::Number(Cow::Borrowed(n)),
+            Value::String(s) => JsonKind::String(s),
+            Value::Array(a) => JsonKind::Array(a.len()),
+            Value::Object(m) => JsonKind::Object(m.len()),
+        }
+    }
+
+    fn children(self) -> Box<dyn Iterator<Item = JsonChild<'a, Self>> + 'a> {
+        match self {
+            Value::Object(map) => Box::new(map.iter().map(|(k, v)| (Cow::Borrowed(k.as_str()), v))),
+            Value::Array(arr) => Box::new(arr.iter().enumerate().map(|(i, v)| (Cow::Owned(i.to_string()), v))),
+            _ => Box::new(std::iter::empty()),
+        }
+    }
+
+    fn get(self, token: &str) -> Option<Self> {
+        match self {
+            Value::Object(map) => map.get(token),
+            Value::Array(arr) => arr.get(parse_index(token)?),
+            _ => None,
+        }
+    }
+
+    fn to_value(self) -> Value {
+        self.clone()
+    }
+
+    fn pointer(self, pointer: &str) -> Option<Self> {
+        Value::pointer(self, pointer)
+    }
+}
+
+// Approximate heap bytes held by a Value tree: string, number and array buffers, plus each
+// object's IndexMap (an entry vector and a hash table of indices, both grown by doubling from
+// 3 slots, since parsed maps are built one insert at a time)
+pub fn value_heap_bytes(value: &Value) -> usize {
+    const ENTRY: usize = size_of::<u64>() + size_of::<String>() + size_of::<Value>();
+    const GROUP_WIDTH: usize = 16;
+    match value {
+        Value::String(s) => s.capacity(),
+        // Parsed number text is pushed a digit at a time
+        Value::Number(n) => n.as_str().len().next_power_of_two().max(8),
+        Value::Array(arr) => arr.capacity() * size_of::<Value>() + arr.iter().map(value_heap_bytes).sum::<usize>(),
+        Value::Object(map) => {
+            let table = match map.len() {
+                0 => 0,
+                n => {
+                    let buckets = (2..).map(|shift| 1usize << shift).find(|&b| table_capacity(b) >= n).unwrap_or(0);
+                    buckets * (size_of::<usize>() + 1) + GROUP_WIDTH + table_capacity(buckets) * ENTRY
+                }
+            };
+            table + map.iter().map(|(k, v)| k.capacity() + value_heap_bytes(v)).sum::<usize>()
+        }
+        _ => 0,
+    }
+}
+
+// Entries a hash table with `buckets` buckets holds before growing (an eighth is kept free)
+fn table_capacity(buckets: usize) -> usize {
+    if buckets < 8 { buckets - 1 } else { buckets / 8 * 7 }
+}
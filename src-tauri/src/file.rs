@@ -1,9 +1,23 @@
-use std::{fs::File, io::{BufReader, Read}, sync::Arc};
+use std::{fs::File, io::{BufRead, BufReader, BufWriter, Read, Write}, path::Path, sync::{atomic::AtomicBool, Arc}};
+use serde::Serialize;
 use serde_json::Value;
 use tauri::{async_runtime::spawn_blocking, Emitter};
-use crate::state::AppState;
-use crate::types::Node;
+use crate::state::{AppState, MainDocument};
+use crate::types::{DocumentInfo, DuplicateKey, LineError, MemoryUsage, Node, OpenError};
+use crate::parse_error;
+use crate::lint;
+use crate::recover;
 use crate::tree::list_children;
+use crate::lazy::LazyDocument;
+use crate::tape::TapeDocument;
+use crate::document::value_heap_bytes;
+
+// Cap on stored per-line NDJSON errors so a misdetected file can't flood memory / the UI
+const MAX_LINE_ERRORS: usize = 1000;
+// Likewise for duplicate key warnings; lint_document lists them all
+const MAX_DUPLICATE_KEYS: usize = 1000;
+// How much of the file we inspect when sniffing for NDJSON
+const SNIFF_BYTES: u64 = 64 * 1024;
 
 // Progress reader for tracking file loading progress
 struct ProgressReader<R: Read> {
@@ -16,6 +30,22 @@ struct ProgressReader<R: Read> {
     cancel: Arc<std::sync::atomic::AtomicBool>,
 }
 
+impl ProgressReader<File> {
+    fn open(path: &str, cancel: Arc<AtomicBool>, app_handle: tauri::AppHandle) -> Result<Self, String> {
+        let f = File::open(path).map_err(|e| e.to_string())?;
+        let total_bytes = f.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(ProgressReader {
+            inner: f,
+            read_bytes: 0,
+            total_bytes,
+            last_emit: 0,
+            app_handle,
+            path: path.to_string(),
+            cancel,
+        })
+    }
+}
+
 impl<R: Read> Read for ProgressReader<R> {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         // if canceled, stop reading
@@ -25,75 +55,415 @@ impl<R: Read> Read for ProgressReader<R> {
         let n = self.inner.read(buf)?;
         self.read_bytes += n as u64;
         if self.read_bytes - self.last_emit >= 1024 * 1024 || n == 0 {
-            let percent = if self.total_bytes > 0 { 
-                self.read_bytes as f64 / self.total_bytes as f64 * 100.0 
-            } else { 
-                0.0 
-            };
-            let _ = self.app_handle.emit("parse_progress", serde_json::json!({
-                "path": self.path,
-                "readBytes": self.read_bytes,
-                "totalBytes": self.total_bytes,
-                "percent": percent,
-                "done": n == 0,
-                "canceled": self.cancel.load(std::sync::atomic::Ordering::SeqCst),
-            }));
+            let canceled = self.cancel.load(std::sync::atomic::Ordering::SeqCst);
+            emit_progress(&self.app_handle, &self.path, self.read_bytes, self.total_bytes, n == 0, canceled);
             self.last_emit = self.read_bytes;
         }
         Ok(n)
     }
 }
 
+// "parse_progress" event shared by everything that reads a file for opening
+pub fn emit_progress(app_handle: &tauri::AppHandle, path: &str, read_bytes: u64, total_bytes: u64, done: bool, canceled: bool) {
+    let percent = if total_bytes > 0 { 
+        read_bytes as f64 / total_bytes as f64 * 100.0 
+    } else { 
+        0.0 
+    };
+    let _ = app_handle.emit("parse_progress", serde_json::json!({
+        "path": path,
+        "readBytes": read_bytes,
+        "totalBytes": total_bytes,
+        "percent": percent,
+        "done": done,
+        "canceled": canceled,
+    }));
+}
+
+// NDJSON / JSON Lines detection: by extension first, otherwise sniff the head of the file.
+// A file is treated as NDJSON when its first non-empty line is a complete JSON value on its own
+// and at least one more non-empty line follows. Pretty-printed JSON fails the first check
+// ("{" alone does not parse) and minified single-line JSON fails the second.
+pub fn is_ndjson(path: &str) -> bool {
+    let lower = path.to_lowercase();
+    if lower.ends_with(".ndjson") || lower.ends_with(".jsonl") {
+        return true;
+    }
+    let Ok(f) = File::open(path) else { return false; };
+    let mut head = Vec::new();
+    if f.take(SNIFF_BYTES).read_to_end(&mut head).is_err() {
+        return false;
+    }
+    let mut lines = head
+        .split(|b| *b == b'\n')
+        .filter(|l| !l.iter().all(u8::is_ascii_whitespace));
+    let Some(first) = lines.next() else { return false; };
+    if serde_json::from_slice::<Value>(first).is_err() {
+        return false;
+    }
+    lines.next().is_some()
+}
+
+// Parse NDJSON into a virtual root array. Lines that fail to parse are skipped and reported
+// with their 1-based line number instead of aborting the whole load.
+fn parse_ndjson<R: BufRead>(mut reader: R, cancel: &AtomicBool) -> Result<(Value, Vec<LineError>, usize), String> {
+    let mut items = Vec::new();
+    let mut errors = Vec::new();
+    let mut error_count = 0;
+    let mut line_no = 0;
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        let n = reader.read_until(b'\n', &mut buf).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        line_no += 1;
+        if buf.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        match serde_json::from_slice::<Value>(&buf) {
+            Ok(v) => items.push(v),
+            Err(e) => {
+                error_count += 1;
+                if errors.len() < MAX_LINE_ERRORS {
+                    errors.push(LineError { line: line_no, message: e.to_string() });
+                }
+            }
+        }
+    }
+    // ProgressReader reports EOF once canceled, so a short read here means the user aborted
+    if cancel.load(std::sync::atomic::Ordering::SeqCst) {
+        return Err("Parse canceled".into());
+    }
+    Ok((Value::Array(items), errors, error_count))
+}
+
+// Lenient parse accepting JSON5 / JSONC syntax (comments, trailing commas, unquoted keys,
+// single-quoted strings). Used for config files like tsconfig.json or VS Code settings.
+fn parse_lenient(text: &str) -> Result<Value, OpenError> {
+    json5::from_str(text).map_err(|e| parse_error::from_json5(text, &e))
+}
+
+// Structured error for a failed strict parse of the file at `path`
+fn strict_parse_error(path: &str, e: &serde_json::Error) -> OpenError {
+    match File::open(path) {
+        Ok(f) => parse_error::from_serde(f, e),
+        Err(_) => OpenError::from(e.to_string()),
+    }
+}
+
+// Duplicate object keys in a file that just parsed, for DocumentInfo. The scan is a second pass
+// over the file; if it fails only the warnings are lost.
+fn find_duplicate_keys(path: &str, ndjson: bool) -> (Vec<DuplicateKey>, usize) {
+    lint::file_duplicate_keys(path, ndjson, MAX_DUPLICATE_KEYS).unwrap_or_default()
+}
+
+// Load whatever can be salvaged from the malformed contents of a file, with the repairs that
+// were needed
+fn recover_document(path: String, bytes: &[u8]) -> (Value, DocumentInfo) {
+    let (root, repairs, repair_count) = recover::recover(bytes);
+    (root, DocumentInfo {
+        path: Some(path),
+        format: "json".into(),
+        repairs,
+        repair_count,
+        ..Default::default()
+    })
+}
+
+// Make a freshly parsed value the loaded document, resetting all per-document state.
+// Returns the top-level nodes (first page).
+fn install_document(state: &AppState, root: Value, info: DocumentInfo) -> Vec<Node> {
+    let arc = Arc::new(root);
+    let top = list_children(arc.as_ref(), "", 0, 100);
+    *state.doc.write() = Some(arc);
+    *state.lazy_doc.write() = None;
+    *state.tape_doc.write() = None;
+    reset_document_state(state, info);
+    top
+}
+
+// Same for a compact (tape) document, which takes the place of the Value tree
+fn install_compact_document(state: &AppState, tape: TapeDocument, info: DocumentInfo) -> Vec<Node> {
+    let tape = Arc::new(tape);
+    let top = list_children(tape.root(), "", 0, 100);
+    *state.tape_doc.write() = Some(tape);
+    *state.doc.write() = None;
+    *state.lazy_doc.write() = None;
+    reset_document_state(state, info);
+    top
+}
+
+// Same for a memory-mapped document, which takes the place of the parsed one
+fn install_lazy_document(state: &AppState, lazy: LazyDocument, info: DocumentInfo) -> Result<Vec<Node>, String> {
+    let top = lazy.list_children("", 0, 100)?;
+    *state.lazy_doc.write() = Some(Arc::new(lazy));
+    *state.doc.write() = None;
+    *state.tape_doc.write() = None;
+    reset_document_state(state, info);
+    Ok(top)
+}
+
+fn reset_document_state(state: &AppState, info: DocumentInfo) {
+    *state.doc_info.write() = info;
+    *state.result_doc.write() = None;
+    state.invalidate_derived();
+    state.dirty.store(false, std::sync::atomic::Ordering::SeqCst);
+    state.history.lock().clear();
+}
+
+// Read and parse a document from disk: NDJSON when `ndjson` (see is_ndjson), otherwise JSON with
+// the `lenient` and `recover` handling described on open_file. Emits progress events while reading.
+// `scan_duplicates` fills DocumentInfo.duplicate_keys, which takes a second pass over the source.
+pub fn read_document(path: String, ndjson: bool, lenient: Option<bool>, recover: bool, scan_duplicates: bool, cancel_flag: Arc<AtomicBool>, app_handle: tauri::AppHandle) -> Result<(Value, DocumentInfo), OpenError> {
+    let mut progress = ProgressReader::open(&path, cancel_flag.clone(), app_handle)?;
+    if ndjson && lenient != Some(true) {
+        let (root, line_errors, line_error_count) = parse_ndjson(BufReader::new(progress), &cancel_flag)?;
+        let (duplicate_keys, duplicate_key_count) = if scan_duplicates { find_duplicate_keys(&path, true) } else { Default::default() };
+        return Ok((root, DocumentInfo {
+            path: Some(path),
+            format: "ndjson".into(),
+            line_errors,
+            line_error_count,
+            duplicate_keys,
+            duplicate_key_count,
+            ..Default::default()
+        }));
+    }
+
+    // Read the file once; the lenient parse, its fallback and recovery all work on these bytes
+    let mut bytes = Vec::with_capacity(progress.total_bytes as usize);
+    progress.read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+    // ProgressReader reports EOF once canceled
+    if cancel_flag.load(std::sync::atomic::Ordering::SeqCst) {
+        return Err(OpenError::from("Parse canceled".to_string()));
+    }
+    let lenient_document = |root| (root, DocumentInfo {
+        path: Some(path.clone()),
+        format: "json5".into(),
+        lenient: true,
+        ..Default::default()
+    });
+
+    if lenient == Some(true) {
+        let text = std::str::from_utf8(&bytes).map_err(|e| e.to_string())?;
+        return match parse_lenient(text) {
+            Ok(root) => Ok(lenient_document(root)),
+            Err(_) if recover => Ok(recover_document(path, &bytes)),
+            Err(e) => Err(e),
+        };
+    }
+    match serde_json::from_slice::<Value>(&bytes) {
+        Ok(root) => {
+            // Scan the bytes already in memory rather than reading the file again
+            let (duplicate_keys, duplicate_key_count) = if scan_duplicates {
+                lint::duplicate_keys(std::io::Cursor::new(&bytes), "", MAX_DUPLICATE_KEYS).unwrap_or_default()
+            } else {
+                Default::default()
+            };
+            Ok((root, DocumentInfo {
+                path: Some(path),
+                format: "json".into(),
+                duplicate_keys,
+                duplicate_key_count,
+                ..Default::default()
+            }))
+        }
+        Err(e) => {
+            // Strict parse failed: retry leniently, then recover if asked to, but report the
+            // strict error if nothing works
+            if lenient != Some(false) && e.is_syntax() {
+                if let Some(root) = std::str::from_utf8(&bytes).ok().and_then(|text| parse_lenient(text).ok()) {
+                    return Ok(lenient_document(root));
+                }
+            }
+            if recover && (e.is_syntax() || e.is_eof()) {
+                return Ok(recover_document(path, &bytes));
+            }
+            Err(parse_error::from_serde(std::io::Cursor::new(&bytes), &e))
+        }
+    }
+}
+
+// Parse strict JSON into the compact tape representation, with the same progress events and
+// cancellation as read_document
+fn read_compact_document(path: &str, cancel_flag: Arc<AtomicBool>, app_handle: tauri::AppHandle) -> Result<TapeDocument, OpenError> {
+    let reader = BufReader::new(ProgressReader::open(path, cancel_flag.clone(), app_handle)?);
+    TapeDocument::from_reader(reader).map_err(|e| {
+        if cancel_flag.load(std::sync::atomic::Ordering::SeqCst) {
+            OpenError::from("Parse canceled".to_string())
+        } else {
+            strict_parse_error(path, &e)
+        }
+    })
+}
+
+// `lenient`: Some(true) parses as JSON5 directly, Some(false) is strict only, and None (default)
+// tries strict JSON first and falls back to lenient parsing if that fails.
+// `lazy`: Some(true) memory-maps the file and reads nodes from it on demand instead of parsing
+// it; otherwise (default) the file is parsed.
+// Only plain JSON is loaded lazily; NDJSON and lenient parses always build the full tree. A lazy
+// document can be browsed and its values read, but not edited, searched or transformed.
+// `compact`: Some(true) keeps a strict JSON file in the compact tape representation, which needs
+// a fraction of the memory of a Value tree for browsing, searching and other read-only commands;
+// the first edit, transform, diff or save converts it to a regular tree.
+// Object keys that occur more than once in a strict JSON or NDJSON file are listed in
+// DocumentInfo.duplicate_keys with the position and value of every occurrence; lazily loaded files
+// are not scanned up front (lint_document checks any file).
+// `recover`: Some(true) loads malformed or truncated JSON that neither the strict nor the lenient
+// parse accepts as far as possible, closing unterminated strings and containers and skipping bad
+// tokens; DocumentInfo.repairs then lists every fix with its source offset.
+// Otherwise malformed input fails with an OpenError whose `parse` gives line, column, offset, the source
+// lines around the error and a guess at the cause.
 #[tauri::command]
-pub async fn open_file(path: String, state: tauri::State<'_, AppState>, app_handle: tauri::AppHandle) -> Result<Vec<Node>, String> {
+pub async fn open_file(path: String, lenient: Option<bool>, lazy: Option<bool>, compact: Option<bool>, recover: Option<bool>, state: tauri::State<'_, AppState>, app_handle: tauri::AppHandle) -> Result<Vec<Node>, OpenError> {
     let path_clone = path.clone();
     let handle_clone = app_handle.clone();
     // obtain a cancellation flag clone to share with background thread
     let cancel_flag = state.cancel_parse.clone();
     // reset cancel flag at the beginning of a new parse
     cancel_flag.store(false, std::sync::atomic::Ordering::SeqCst);
-    
-    let root: Value = spawn_blocking(move || {
-        let f = File::open(&path_clone).map_err(|e| e.to_string())?;
-        let metadata = f.metadata().ok();
-        let total_bytes = metadata.map(|m| m.len()).unwrap_or(0);
 
-        let progress_reader = ProgressReader {
-            inner: f,
-            read_bytes: 0,
-            total_bytes,
-            last_emit: 0,
-            app_handle: handle_clone,
-            path: path_clone,
-            cancel: cancel_flag,
-        };
-        let reader = BufReader::new(progress_reader);
-        serde_json::from_reader(reader).map_err(|e| e.to_string())
-    })
-    .await
-    .map_err(|e| format!("Join error: {e}"))??;
+    let recover = recover == Some(true);
+    let ndjson = is_ndjson(&path);
+    if lenient != Some(true) && lazy == Some(true) && !ndjson {
+        let (path_lazy, handle_lazy, cancel_lazy) = (path.clone(), app_handle.clone(), cancel_flag.clone());
+        let opened = spawn_blocking(move || LazyDocument::open(&path_lazy, &cancel_lazy, &handle_lazy))
+            .await
+            .map_err(|e| format!("Join error: {e}"))?;
+        match opened {
+            Ok(doc) => {
+                let info = DocumentInfo {
+                    path: Some(path),
+                    format: "json".into(),
+                    lazy: true,
+                    ..Default::default()
+                };
+                return Ok(install_lazy_document(&state, doc, info)?);
+            }
+            Err(e) if !recover || cancel_flag.load(std::sync::atomic::Ordering::SeqCst) => return Err(e.into()),
+            // Malformed: recovery needs the parsed tree
+            Err(_) => {}
+        }
+    }
+    if compact == Some(true) && lenient != Some(true) && !ndjson {
+        let (path_compact, handle_compact, cancel_compact) = (path.clone(), app_handle.clone(), cancel_flag.clone());
+        let parsed = spawn_blocking(move || read_compact_document(&path_compact, cancel_compact, handle_compact))
+            .await
+            .map_err(|e| format!("Join error: {e}"))?;
+        match parsed {
+            Ok(tape) => {
+                let scan_path = path.clone();
+                let (duplicate_keys, duplicate_key_count) = spawn_blocking(move || find_duplicate_keys(&scan_path, false))
+                    .await
+                    .map_err(|e| format!("Join error: {e}"))?;
+                let info = DocumentInfo {
+                    path: Some(path),
+                    format: "json".into(),
+                    compact: true,
+                    duplicate_keys,
+                    duplicate_key_count,
+                    ..Default::default()
+                };
+                return Ok(install_compact_document(&state, tape, info));
+            }
+            Err(e) if (lenient == Some(false) && !recover) || cancel_flag.load(std::sync::atomic::Ordering::SeqCst) => return Err(e),
+            // Not strict JSON: the regular path below retries with lenient parsing and recovery
+            Err(_) => {}
+        }
+    }
 
-    let arc = Arc::new(root);
-    let top = list_children(&arc, "", 0, 100);
-    *state.doc.write() = Some(arc);
-    Ok(top)
+    let (root, info) = spawn_blocking(move || read_document(path_clone, ndjson, lenient, recover, true, cancel_flag, handle_clone))
+        .await
+        .map_err(|e| format!("Join error: {e}"))??;
+
+    Ok(install_document(&state, root, info))
 }
 
 // Load JSON from the system clipboard (expects UTF-8 text containing a JSON value).
 // Replaces the currently loaded document (if any) after confirmation on the frontend.
 // Returns the top-level nodes (first page) similar to open_file.
+// `lenient` and `recover` behave as in open_file.
 #[tauri::command]
-pub fn open_clipboard(state: tauri::State<'_, AppState>) -> Result<Vec<Node>, String> {
+pub fn open_clipboard(lenient: Option<bool>, recover: Option<bool>, state: tauri::State<'_, AppState>) -> Result<Vec<Node>, OpenError> {
     use arboard::Clipboard;
     let mut cb = Clipboard::new().map_err(|e| format!("Clipboard init failed: {e}"))?;
     let text = cb.get_text().map_err(|e| format!("Failed reading clipboard text: {e}"))?;
     // Parse JSON
-    let root: Value = serde_json::from_str(&text)
-        .map_err(|e| format!("Clipboard does not contain valid JSON: {e}"))?;
-    let arc = Arc::new(root);
-    let top = list_children(&arc, "", 0, 100);
-    *state.doc.write() = Some(arc);
-    Ok(top)
+    let clipboard_error = |mut err: OpenError, kind: &str| {
+        err.message = format!("Clipboard does not contain valid {kind}: {}", err.message);
+        err
+    };
+    let parsed = if lenient == Some(true) {
+        parse_lenient(&text).map(|root| (root, true)).map_err(|e| clipboard_error(e, "JSON5"))
+    } else {
+        match serde_json::from_str::<Value>(&text) {
+            Ok(root) => Ok((root, false)),
+            Err(e) => {
+                let strict_error = || clipboard_error(parse_error::from_serde(std::io::Cursor::new(text.as_bytes()), &e), "JSON");
+                if lenient.is_some() {
+                    Err(strict_error())
+                } else {
+                    parse_lenient(&text).map(|root| (root, true)).map_err(|_| strict_error())
+                }
+            }
+        }
+    };
+    let (root, info) = match parsed {
+        Ok((root, is_lenient)) => {
+            let (duplicate_keys, duplicate_key_count) = if is_lenient {
+                Default::default()
+            } else {
+                lint::duplicate_keys(std::io::Cursor::new(text.as_bytes()), "", MAX_DUPLICATE_KEYS).unwrap_or_default()
+            };
+            (root, DocumentInfo {
+                format: if is_lenient { "json5".into() } else { "json".into() },
+                lenient: is_lenient,
+                duplicate_keys,
+                duplicate_key_count,
+                ..Default::default()
+            })
+        }
+        Err(_) if recover == Some(true) => {
+            let (root, repairs, repair_count) = recover::recover(text.as_bytes());
+            (root, DocumentInfo {
+                format: "json".into(),
+                repairs,
+                repair_count,
+                ..Default::default()
+            })
+        }
+        Err(e) => return Err(e),
+    };
+    Ok(install_document(&state, root, info))
+}
+
+// Metadata for the loaded document: source format, lenient flag and any per-line NDJSON parse errors.
+#[tauri::command]
+pub fn get_document_info(state: tauri::State<'_, AppState>) -> Result<DocumentInfo, String> {
+    if state.main_document().is_err() && state.lazy_doc.read().is_none() {
+        return Err("No document loaded".into());
+    }
+    Ok(state.doc_info.read().clone())
+}
+
+// Heap memory held by the loaded main document in its current representation
+#[tauri::command]
+pub fn get_memory_usage(state: tauri::State<'_, AppState>) -> Result<MemoryUsage, String> {
+    if let Some(lazy) = state.lazy_doc.read().clone() {
+        return Ok(MemoryUsage { representation: "lazy".into(), bytes: lazy.heap_bytes() });
+    }
+    Ok(match state.main_document()? {
+        MainDocument::Tree(doc) => MemoryUsage { representation: "tree".into(), bytes: value_heap_bytes(&doc) },
+        MainDocument::Compact(doc) => MemoryUsage { representation: "compact".into(), bytes: doc.heap_bytes() },
+    })
+}
+
+// Whether the document has unsaved edits (UI warns before closing / opening another file)
+#[tauri::command]
+pub fn is_dirty(state: tauri::State<'_, AppState>) -> bool {
+    state.dirty.load(std::sync::atomic::Ordering::SeqCst)
 }
 
 #[tauri::command]
@@ -102,11 +472,26 @@ pub fn cancel_parse(state: tauri::State<'_, AppState>) -> Result<(), String> {
     Ok(())
 }
 
+// `source` selects which document to browse (see AppState::document); defaults to the loaded file
 #[tauri::command]
-pub fn load_children(pointer: String, offset: usize, limit: usize, state: tauri::State<'_, AppState>) -> Result<Vec<Node>, String> {
-    let guard = state.doc.read();
-    let Some(root) = &*guard else { return Err("No document loaded".into()); };
-    Ok(list_children(root, &pointer, offset, limit))
+pub fn load_children(pointer: String, offset: usize, limit: usize, source: Option<String>, state: tauri::State<'_, AppState>) -> Result<Vec<Node>, String> {
+    if let (None | Some("main"), Some(lazy)) = (source.as_deref(), state.lazy_doc.read().clone()) {
+        return lazy.list_children(&pointer, offset, limit);
+    }
+    let mut nodes = match source.as_deref() {
+        None | Some("main") => match state.main_document()? {
+            MainDocument::Tree(doc) => list_children(doc.as_ref(), &pointer, offset, limit),
+            MainDocument::Compact(doc) => list_children(doc.root(), &pointer, offset, limit),
+        },
+        _ => list_children(state.document(source.as_deref())?.as_ref(), &pointer, offset, limit),
+    };
+    if let Some(diff) = &*state.diff.read() {
+        diff.annotate(source.as_deref(), &mut nodes);
+    }
+    if let (None | Some("main"), Some(validation)) = (source.as_deref(), &*state.schema_validation.read()) {
+        validation.annotate(&mut nodes);
+    }
+    Ok(nodes)
 }
 
 #[tauri::command]
@@ -119,7 +504,7 @@ pub async fn open_file_dialog(app: tauri::AppHandle) -> Result<Option<String>, S
     // Use the app's dialog interface
     app.dialog()
         .file()
-        .add_filter("JSON files", &["json"])
+        .add_filter("JSON files", &["json", "json5", "jsonc", "ndjson", "jsonl"])
         .set_title("Open JSON File")
         .pick_file(move |file_path| {
             let result = file_path.map(|p| p.to_string());
@@ -131,4 +516,150 @@ pub async fn open_file_dialog(app: tauri::AppHandle) -> Result<Option<String>, S
         Ok(result) => Ok(result),
         Err(_) => Err("Dialog was cancelled or failed".to_string()),
     }
+}
+
+// Serialize the document. NDJSON documents are written back as one compact element per line;
+// everything else is pretty-printed with `indent` spaces, or minified.
+fn write_document<W: Write>(mut out: W, root: &Value, format: &str, indent: usize, minify: bool) -> Result<(), String> {
+    if let (true, Value::Array(items)) = (format == "ndjson", root) {
+        for item in items {
+            serde_json::to_writer(&mut out, item).map_err(|e| e.to_string())?;
+            out.write_all(b"\n").map_err(|e| e.to_string())?;
+        }
+        return Ok(());
+    }
+    if minify {
+        serde_json::to_writer(&mut out, root).map_err(|e| e.to_string())?;
+    } else {
+        let indent_str = " ".repeat(indent);
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(indent_str.as_bytes());
+        let mut ser = serde_json::Serializer::with_formatter(&mut out, formatter);
+        root.serialize(&mut ser).map_err(|e| e.to_string())?;
+        out.write_all(b"\n").map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// Write to a temp file next to the target and rename it over the original, so a failed or
+// interrupted save never leaves a truncated file behind.
+pub fn write_atomically(path: &Path, root: &Value, format: &str, indent: usize, minify: bool) -> Result<(), String> {
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let file_name = path.file_name().ok_or("Invalid file path")?.to_string_lossy();
+    let tmp_path = dir.join(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+    let result = (|| {
+        let f = File::create(&tmp_path).map_err(|e| format!("Failed to create temp file: {e}"))?;
+        let mut writer = BufWriter::new(f);
+        write_document(&mut writer, root, format, indent, minify)?;
+        let f = writer.into_inner().map_err(|e| e.to_string())?;
+        f.sync_all().map_err(|e| e.to_string())?;
+        std::fs::rename(&tmp_path, path).map_err(|e| format!("Failed to replace file: {e}"))
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+// What writing the loaded document as strict JSON would drop from its source, if anything
+fn lossy_save_warning(info: &DocumentInfo) -> Option<String> {
+    let mut lost = Vec::new();
+    if info.lenient {
+        lost.push("comments and other JSON5 syntax from the lenient source".to_string());
+    }
+    if info.repair_count > 0 {
+        lost.push(format!("the original text at {} repaired locations", info.repair_count));
+    }
+    if info.duplicate_key_count > 0 {
+        lost.push(format!("all but the last value of {} duplicated keys", info.duplicate_key_count));
+    }
+    (!lost.is_empty()).then(|| format!("Saving writes strict JSON and loses {}; save again with confirm_lossy to proceed", lost.join(", ")))
+}
+
+// `confirm_lossy` must be set to save a document whose source would not survive the round trip
+// (see lossy_save_warning); without it the save is refused with an explanation
+async fn save_to(path: String, format: String, indent: Option<usize>, minify: Option<bool>, confirm_lossy: Option<bool>, state: &AppState) -> Result<String, String> {
+    if confirm_lossy != Some(true) {
+        if let Some(warning) = lossy_save_warning(&state.doc_info.read()) {
+            return Err(warning);
+        }
+    }
+    let root_arc = state.document(None)?;
+    let snapshot = root_arc.clone();
+    let target = path.clone();
+    let write_format = format.clone();
+    spawn_blocking(move || {
+        write_atomically(Path::new(&target), &root_arc, &write_format, indent.unwrap_or(2), minify.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| format!("Join error: {e}"))??;
+
+    // Only clear the dirty flag if no edit landed while we were writing
+    let unchanged = state.doc.read().as_ref().is_some_and(|d| Arc::ptr_eq(d, &snapshot));
+    if unchanged {
+        state.dirty.store(false, std::sync::atomic::Ordering::SeqCst);
+    }
+    let mut info = state.doc_info.write();
+    info.path = Some(path.clone());
+    info.format = format;
+    // The file on disk is now strict JSON; comments from a lenient source are gone, repairs from
+    // a recovered one no longer point into it and duplicate keys were written once
+    info.lenient = false;
+    info.repairs.clear();
+    info.repair_count = 0;
+    info.duplicate_keys.clear();
+    info.duplicate_key_count = 0;
+    Ok(path)
+}
+
+// Save the document back to the file it was opened from. Returns the written path.
+// Documents loaded leniently, through recovery or with duplicate keys need `confirm_lossy`.
+#[tauri::command]
+pub async fn save_file(indent: Option<usize>, minify: Option<bool>, confirm_lossy: Option<bool>, state: tauri::State<'_, AppState>) -> Result<String, String> {
+    let (path, format) = {
+        let info = state.doc_info.read();
+        let Some(path) = info.path.clone() else {
+            return Err("Document has no file path; use Save As".into());
+        };
+        let format = if info.format == "ndjson" { "ndjson" } else { "json" };
+        (path, format.to_string())
+    };
+    save_to(path, format, indent, minify, confirm_lossy, &state).await
+}
+
+// Save the document to a new path, which becomes the document's path for subsequent saves.
+// A .ndjson/.jsonl target with an array root is written as JSON Lines. `confirm_lossy` as in
+// save_file.
+#[tauri::command]
+pub async fn save_file_as(path: String, indent: Option<usize>, minify: Option<bool>, confirm_lossy: Option<bool>, state: tauri::State<'_, AppState>) -> Result<String, String> {
+    let lower = path.to_lowercase();
+    let root_is_array = state.document(None).is_ok_and(|d| d.is_array());
+    let format = if (lower.ends_with(".ndjson") || lower.ends_with(".jsonl")) && root_is_array {
+        "ndjson"
+    } else {
+        "json"
+    };
+    save_to(path, format.to_string(), indent, minify, confirm_lossy, &state).await
+}
+
+#[tauri::command]
+pub async fn save_file_dialog(app: tauri::AppHandle) -> Result<Option<String>, String> {
+    use tauri_plugin_dialog::DialogExt;
+    use tokio::sync::oneshot;
+
+    let (tx, rx) = oneshot::channel();
+
+    app.dialog()
+        .file()
+        .add_filter("JSON files", &["json", "ndjson", "jsonl"])
+        .set_title("Save JSON File")
+        .save_file(move |file_path| {
+            let result = file_path.map(|p| p.to_string());
+            let _ = tx.send(result);
+        });
+
+    match rx.await {
+        Ok(result) => Ok(result),
+        Err(_) => Err("Dialog was cancelled or failed".to_string()),
+    }
 }
\ No newline at end of file
@@ -0,0 +1,459 @@
+use std::cmp::Ordering;
+use crate::document::{JsonKind, JsonNode};
+
+// Structured search filters, evaluated natively against each key/value pair instead of
+// stringifying values. Syntax:
+//
+//   filter    := condition (("and" | "or") condition)*      ("and" binds tighter)
+//   condition := KEY predicate
+//   predicate := "is" ["not"] "null"
+//              | "type" ("==" | "!=") TYPE                 (object, array, string, number, boolean, null)
+//              | "length" OP NUMBER                        (string chars / array items / object keys)
+//              | OP LITERAL                                (OP: == != > >= < <=)
+//              | "between" LITERAL "and" LITERAL           (inclusive)
+//
+// KEY is a key name or array index and may use `*` / `?` wildcards (`*` alone matches any key).
+// LITERAL is a number, true/false/null, or a string (quoted, or bare like 2024-01-01).
+// Strings that both parse as ISO 8601 dates compare chronologically.
+//
+// Examples: `price > 100`, `* is null`, `tags length > 3`, `created between 2024-01-01 and 2024-06-30`
+
+#[derive(Clone, Copy, PartialEq)]
+enum Cmp { Eq, Ne, Gt, Ge, Lt, Le }
+
+impl Cmp {
+    fn parse(token: &str) -> Option<Self> {
+        Some(match token {
+            "==" | "=" => Cmp::Eq,
+            "!=" => Cmp::Ne,
+            ">" => Cmp::Gt,
+            ">=" => Cmp::Ge,
+            "<" => Cmp::Lt,
+            "<=" => Cmp::Le,
+            _ => return None,
+        })
+    }
+
+    fn holds(self, ord: Ordering) -> bool {
+        match self {
+            Cmp::Eq => ord == Ordering::Equal,
+            Cmp::Ne => ord != Ordering::Equal,
+            Cmp::Gt => ord == Ordering::Greater,
+            Cmp::Ge => ord != Ordering::Less,
+            Cmp::Lt => ord == Ordering::Less,
+            Cmp::Le => ord != Ordering::Greater,
+        }
+    }
+}
+
+enum Literal {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Str(String),
+}
+
+enum Predicate {
+    IsNull { negated: bool },
+    Type { negated: bool, type_name: String },
+    Length(Cmp, f64),
+    Compare(Cmp, Literal),
+    Between(Literal, Literal),
+}
+
+struct Condition {
+    key: regex::Regex,
+    predicate: Predicate,
+}
+
+pub struct StructuredFilter {
+    // OR of AND-groups
+    any_of: Vec<Vec<Condition>>,
+}
+
+impl StructuredFilter {
+    pub fn parse(input: &str, case_sensitive: bool) -> Result<Self, String> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0, case_sensitive };
+        let mut any_of = vec![vec![parser.condition()?]];
+        while let Some(word) = parser.next() {
+            match word.to_lowercase().as_str() {
+                "and" => any_of.last_mut().unwrap().push(parser.condition()?),
+                "or" => any_of.push(vec![parser.condition()?]),
+                _ => return Err(format!("Invalid filter: expected `and` or `or`, found `{word}`")),
+            }
+        }
+        Ok(Self { any_of })
+    }
+
+    // `key` is the object key or array index (as string) of `value` within its parent
+    pub fn matches<'a>(&self, key: &str, value: impl JsonNode<'a>) -> bool {
+        self.any_of.iter().any(|group| group.iter().all(|c| c.matches(key, value)))
+    }
+}
+
+impl Condition {
+    fn matches<'a>(&self, key: &str, value: impl JsonNode<'a>) -> bool {
+        if !self.key.is_match(key) {
+            return false;
+        }
+        match &self.predicate {
+            Predicate::IsNull { negated } => matches!(value.kind(), JsonKind::Null) != *negated,
+            Predicate::Type { negated, type_name } => (value.type_name() == type_name) != *negated,
+            Predicate::Length(cmp, n) => {
+                let len = match value.kind() {
+                    JsonKind::String(s) => s.chars().count(),
+                    JsonKind::Array(len) | JsonKind::Object(len) => len,
+                    _ => return false,
+                };
+                (len as f64).partial_cmp(n).is_some_and(|ord| cmp.holds(ord))
+            }
+            Predicate::Compare(cmp, lit) => compare(value, *cmp, lit),
+            Predicate::Between(lo, hi) => compare(value, Cmp::Ge, lo) && compare(value, Cmp::Le, hi),
+        }
+    }
+}
+
+fn compare<'a>(value: impl JsonNode<'a>, cmp: Cmp, lit: &Literal) -> bool {
+    let ord = match (value.kind(), lit) {
+        (JsonKind::Number(n), Literal::Number(x)) => n.as_f64().and_then(|f| f.partial_cmp(x)),
+        (JsonKind::String(s), Literal::Str(t)) => match (parse_iso_datetime(s), parse_iso_datetime(t)) {
+            (Some(a), Some(b)) => Some(a.cmp(&b)),
+            _ => Some(s.cmp(t.as_str())),
+        },
+        (JsonKind::Bool(a), Literal::Bool(b)) if matches!(cmp, Cmp::Eq | Cmp::Ne) => Some(a.cmp(b)),
+        (JsonKind::Null, Literal::Null) if matches!(cmp, Cmp::Eq | Cmp::Ne) => Some(Ordering::Equal),
+        // Values of different types are never equal and never ordered
+        _ => return cmp == Cmp::Ne,
+    };
+    ord.is_some_and(|o| cmp.holds(o))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    case_sensitive: bool,
+}
+
+#[derive(Clone)]
+enum Token {
+    Word(String),
+    Quoted(String),
+}
+
+impl Token {
+    fn text(&self) -> &str {
+        match self {
+            Token::Word(s) | Token::Quoted(s) => s,
+        }
+    }
+}
+
+impl Parser {
+    fn next_token(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn next(&mut self) -> Option<String> {
+        self.next_token().map(|t| t.text().to_string())
+    }
+
+    fn expect(&mut self, what: &str) -> Result<Token, String> {
+        self.next_token().ok_or_else(|| format!("Invalid filter: expected {what} at end of input"))
+    }
+
+    fn keyword(&mut self, word: &str) -> Result<(), String> {
+        match self.next_token() {
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case(word) => Ok(()),
+            Some(t) => Err(format!("Invalid filter: expected `{word}`, found `{}`", t.text())),
+            None => Err(format!("Invalid filter: expected `{word}` at end of input")),
+        }
+    }
+
+    fn condition(&mut self) -> Result<Condition, String> {
+        let key = self.expect("a key")?;
+        let key = key_pattern(key.text(), self.case_sensitive)?;
+        let op = self.expect("a predicate")?;
+        let predicate = match op {
+            Token::Word(w) if w.eq_ignore_ascii_case("is") => {
+                let next = self.expect("`null` or `not`")?;
+                let negated = next.text().eq_ignore_ascii_case("not");
+                if negated {
+                    self.keyword("null")?;
+                } else if !next.text().eq_ignore_ascii_case("null") {
+                    return Err(format!("Invalid filter: expected `null`, found `{}`", next.text()));
+                }
+                Predicate::IsNull { negated }
+            }
+            Token::Word(w) if w.eq_ignore_ascii_case("type") => {
+                let cmp = self.operator()?;
+                if !matches!(cmp, Cmp::Eq | Cmp::Ne) {
+                    return Err("Invalid filter: `type` supports only == and !=".into());
+                }
+                let type_name = self.expect("a type name")?.text().to_lowercase();
+                let type_name = if type_name == "bool" { "boolean".to_string() } else { type_name };
+                if !["object", "array", "string", "number", "boolean", "null"].contains(&type_name.as_str()) {
+                    return Err(format!("Invalid filter: unknown type `{type_name}`"));
+                }
+                Predicate::Type { negated: cmp == Cmp::Ne, type_name }
+            }
+            Token::Word(w) if w.eq_ignore_ascii_case("length") => {
+                let cmp = self.operator()?;
+                let n = self.expect("a number")?;
+                let n: f64 = n.text().parse().map_err(|_| format!("Invalid filter: expected a number, found `{}`", n.text()))?;
+                Predicate::Length(cmp, n)
+            }
+            Token::Word(w) if w.eq_ignore_ascii_case("between") => {
+                let lo = literal(self.expect("a value")?);
+                self.keyword("and")?;
+                let hi = literal(self.expect("a value")?);
+                Predicate::Between(lo, hi)
+            }
+            Token::Word(w) => {
+                let cmp = Cmp::parse(&w).ok_or_else(|| format!("Invalid filter: unknown predicate `{w}`"))?;
+                Predicate::Compare(cmp, literal(self.expect("a value")?))
+            }
+            Token::Quoted(q) => return Err(format!("Invalid filter: expected a predicate, found \"{q}\"")),
+        };
+        Ok(Condition { key, predicate })
+    }
+
+    fn operator(&mut self) -> Result<Cmp, String> {
+        let t = self.expect("an operator")?;
+        Cmp::parse(t.text()).ok_or_else(|| format!("Invalid filter: expected an operator, found `{}`", t.text()))
+    }
+}
+
+fn literal(token: Token) -> Literal {
+    match token {
+        Token::Quoted(s) => Literal::Str(s),
+        Token::Word(w) => match w.as_str() {
+            "null" => Literal::Null,
+            "true" => Literal::Bool(true),
+            "false" => Literal::Bool(false),
+            _ => w.parse::<f64>().map(Literal::Number).unwrap_or(Literal::Str(w)),
+        },
+    }
+}
+
+// Glob-style key pattern (`*`, `?`) compiled to an anchored regex
+fn key_pattern(glob: &str, case_sensitive: bool) -> Result<regex::Regex, String> {
+    let mut re = String::from(if case_sensitive { "^" } else { "(?i)^" });
+    for c in glob.chars() {
+        match c {
+            '*' => re.push_str(".*"),
+            '?' => re.push('.'),
+            _ => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    regex::Regex::new(&re).map_err(|e| format!("Invalid filter: {e}"))
+}
+
+// Words, quoted strings, and comparison operators (which need no surrounding spaces)
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' || c == '\'' {
+            chars.next();
+            let mut s = String::new();
+            loop {
+                match chars.next() {
+                    Some('\\') => s.extend(chars.next()),
+                    Some(ch) if ch == c => break,
+                    Some(ch) => s.push(ch),
+                    None => return Err("Invalid filter: unterminated string".into()),
+                }
+            }
+            tokens.push(Token::Quoted(s));
+        } else if matches!(c, '=' | '!' | '<' | '>') {
+            let mut op = String::from(c);
+            chars.next();
+            if chars.peek() == Some(&'=') {
+                op.push('=');
+                chars.next();
+            }
+            tokens.push(Token::Word(op));
+        } else {
+            let mut word = String::new();
+            while let Some(&ch) = chars.peek() {
+                if ch.is_whitespace() || matches!(ch, '=' | '!' | '<' | '>' | '"' | '\'') {
+                    break;
+                }
+                word.push(ch);
+                chars.next();
+            }
+            tokens.push(Token::Word(word));
+        }
+    }
+    if tokens.is_empty() {
+        return Err("Invalid filter: empty expression".into());
+    }
+    Ok(tokens)
+}
+
+// Parse an ISO 8601 date or date-time (YYYY-MM-DD[THH:MM[:SS[.fff]]][Z|±HH:MM]) into
+// milliseconds since the Unix epoch. Date-times without an offset are treated as UTC.
+pub fn parse_iso_datetime(s: &str) -> Option<i64> {
+    let b = s.as_bytes();
+    let num = |range: std::ops::Range<usize>| -> Option<i64> {
+        let part = b.get(range)?;
+        if part.iter().all(u8::is_ascii_digit) {
+            std::str::from_utf8(part).ok()?.parse().ok()
+        } else {
+            None
+        }
+    };
+    if b.len() < 10 || b[4] != b'-' || b[7] != b'-' {
+        return None;
+    }
+    let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
+    }
+    let mut millis = days_from_civil(year, month, day) * 86_400_000;
+    if b.len() == 10 {
+        return Some(millis);
+    }
+
+    if !matches!(b[10], b'T' | b't' | b' ') || b.len() < 16 || b[13] != b':' {
+        return None;
+    }
+    let (hour, minute) = (num(11..13)?, num(14..16)?);
+    let mut pos = 16;
+    let mut second = 0;
+    if b.get(pos) == Some(&b':') {
+        second = num(pos + 1..pos + 3)?;
+        pos += 3;
+    }
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    millis += ((hour * 60 + minute) * 60 + second) * 1000;
+    if b.get(pos) == Some(&b'.') {
+        let start = pos + 1;
+        pos = start;
+        while b.get(pos).is_some_and(u8::is_ascii_digit) {
+            pos += 1;
+        }
+        if pos == start {
+            return None;
+        }
+        let frac = &s[start..pos.min(start + 3)];
+        millis += frac.parse::<i64>().ok()? * 10i64.pow(3 - frac.len() as u32);
+    }
+    match b.get(pos) {
+        None => Some(millis),
+        Some(b'Z' | b'z') if pos + 1 == b.len() => Some(millis),
+        Some(sign @ (b'+' | b'-')) => {
+            let oh = num(pos + 1..pos + 3)?;
+            let om = match b.len() - pos {
+                3 => 0,
+                5 => num(pos + 3..pos + 5)?,
+                6 if b[pos + 3] == b':' => num(pos + 4..pos + 6)?,
+                _ => return None,
+            };
+            let offset = (oh * 60 + om) * 60_000;
+            Some(if *sign == b'+' { millis - offset } else { millis + offset })
+        }
+        _ => None,
+    }
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's algorithm)
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = (if y >= 0 { y } else { y - 399 }) / 400;
+    let yoe = y - era * 400;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn matches(filter: &str, key: &str, value: Value) -> bool {
+        StructuredFilter::parse(filter, false).unwrap().matches(key, &value)
+    }
+
+    fn parse_error(filter: &str) -> String {
+        StructuredFilter::parse(filter, false).err().expect("filter should not parse")
+    }
+
+    #[test]
+    fn operators_parse_with_or_without_spaces() {
+        assert!(matches("price > 100", "price", json!(150)));
+        assert!(matches("price>100", "price", json!(150)));
+        assert!(!matches("price >= 100", "price", json!(99.5)));
+        assert!(matches("price <= 100", "price", json!(100)));
+        assert!(matches("price = 100", "price", json!(100)));
+        assert!(matches("price != 100", "price", json!(101)));
+        assert!(matches("tags length > 1", "tags", json!(["a", "b"])));
+        assert!(matches("name is not null", "name", json!("x")));
+        assert!(matches("* is null", "anything", json!(null)));
+        assert!(matches("id type == number", "id", json!(1)));
+        assert!(matches("n between 1 and 3 or n == 10", "n", json!(10)));
+        assert!(!matches("n > 1 and n < 3", "n", json!(5)));
+        assert!(matches("us?r_* == 'a b'", "user_name", json!("a b")));
+    }
+
+    #[test]
+    fn malformed_filters_are_rejected() {
+        assert!(parse_error("").contains("empty expression"));
+        assert!(parse_error("price").contains("expected a predicate"));
+        assert!(parse_error("price ~ 1").contains("unknown predicate"));
+        assert!(parse_error("price > 1 nand x > 2").contains("expected `and` or `or`"));
+        assert!(parse_error("id type > number").contains("only == and !="));
+        assert!(parse_error("id type == date").contains("unknown type"));
+        assert!(parse_error("tags length > many").contains("expected a number"));
+        assert!(parse_error("name == 'open").contains("unterminated string"));
+    }
+
+    #[test]
+    fn values_of_other_types_never_compare() {
+        assert!(!matches("price > 100", "price", json!("150")));
+        assert!(!matches("price == 1", "price", json!(true)));
+        assert!(matches("price != 1", "price", json!("1")));
+        assert!(!matches("flag > false", "flag", json!(true)));
+        assert!(!matches("name length > 0", "name", json!(5)));
+        assert!(!matches("n between 1 and 3", "n", json!(null)));
+    }
+
+    #[test]
+    fn dates_compare_chronologically() {
+        assert!(matches("created between 2024-01-01 and 2024-06-30", "created", json!("2024-03-15T10:00:00Z")));
+        assert!(matches("created > 2024-01-01T00:00:00+01:00", "created", json!("2023-12-31T23:30:00Z")));
+        assert_eq!(parse_iso_datetime("1970-01-02"), Some(86_400_000));
+        assert_eq!(parse_iso_datetime("1970-01-01T00:00:01.5Z"), Some(1500));
+    }
+
+    #[test]
+    fn invalid_dates_are_rejected() {
+        assert!(parse_iso_datetime("2024-02-29").is_some());
+        assert!(parse_iso_datetime("2000-02-29").is_some());
+        assert!(parse_iso_datetime("2023-02-29").is_none());
+        assert!(parse_iso_datetime("1900-02-29").is_none());
+        assert!(parse_iso_datetime("2024-02-31").is_none());
+        assert!(parse_iso_datetime("2024-04-31").is_none());
+        assert!(parse_iso_datetime("2024-13-01").is_none());
+        assert!(parse_iso_datetime("2024-01-01T24:00").is_none());
+        assert!(parse_iso_datetime("2024-01-01T10:00+1").is_none());
+    }
+}
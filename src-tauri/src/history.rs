@@ -0,0 +1,218 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use serde_json::Value;
+use crate::state::AppState;
+use crate::types::{HistoryStatus, Node};
+use crate::tree::{build_node_for_pointer, insert_at_pointer, remove_at_pointer, split_pointer, value_at_mut};
+
+// Default memory budget for the edit journal (old + new values of every recorded edit)
+pub const DEFAULT_HISTORY_BUDGET: usize = 256 * 1024 * 1024;
+
+// A single recorded mutation at a JSON Pointer. `index` is the position within the parent
+// container, so removed object keys can be restored where they were.
+#[derive(Clone)]
+pub enum EditOp {
+    Replace { pointer: String, old: Value, new: Value },
+    Add { pointer: String, index: usize, value: Value },
+    Remove { pointer: String, index: usize, value: Value },
+}
+
+impl EditOp {
+    // Pointer of the node whose display changes: the value itself for replacements,
+    // the parent container when a child is added or removed
+    fn affected_pointer(&self) -> &str {
+        match self {
+            EditOp::Replace { pointer, .. } => pointer,
+            EditOp::Add { pointer, .. } | EditOp::Remove { pointer, .. } => {
+                split_pointer(pointer).map(|(parent, _)| parent).unwrap_or("")
+            }
+        }
+    }
+
+    fn approx_size(&self) -> usize {
+        match self {
+            EditOp::Replace { pointer, old, new } => pointer.len() + approx_value_size(old) + approx_value_size(new),
+            EditOp::Add { pointer, value, .. } | EditOp::Remove { pointer, value, .. } => pointer.len() + approx_value_size(value),
+        }
+    }
+}
+
+// One undoable step; a command may produce several ops that are undone together
+pub struct Edit {
+    pub label: String,
+    pub ops: Vec<EditOp>,
+    size: usize,
+}
+
+impl Edit {
+    pub fn new(label: &str, ops: Vec<EditOp>) -> Self {
+        let size = ops.iter().map(EditOp::approx_size).sum();
+        Self { label: label.to_string(), ops, size }
+    }
+}
+
+pub struct History {
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+    used: usize,
+    budget: usize,
+    incomplete: bool, // undo no longer reaches back to the document as loaded
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self { undo: VecDeque::new(), redo: Vec::new(), used: 0, budget: DEFAULT_HISTORY_BUDGET, incomplete: false }
+    }
+}
+
+impl History {
+    // Record a freshly applied edit. Clears the redo stack and evicts the oldest entries until the
+    // journal fits the budget. An edit larger than the whole budget is not kept at all.
+    pub fn record(&mut self, edit: Edit) {
+        for discarded in self.redo.drain(..) {
+            self.used -= discarded.size;
+        }
+        if edit.size > self.budget {
+            self.clear();
+            self.incomplete = true;
+            return;
+        }
+        self.used += edit.size;
+        self.undo.push_back(edit);
+        self.evict();
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.used = 0;
+        self.incomplete = false;
+    }
+
+    // Edits applied since the document was loaded, oldest first; None if some were evicted
+    pub fn applied_edits(&self) -> Option<impl Iterator<Item = &Edit>> {
+        (!self.incomplete).then(|| self.undo.iter())
+    }
+
+    // `used` covers both stacks; undo entries go first, then the redo entries furthest from now
+    fn evict(&mut self) {
+        while self.used > self.budget {
+            let dropped = match self.undo.pop_front() {
+                Some(e) => {
+                    self.incomplete = true;
+                    e
+                }
+                None if !self.redo.is_empty() => self.redo.remove(0),
+                None => break,
+            };
+            self.used -= dropped.size;
+        }
+    }
+
+    fn status(&self) -> HistoryStatus {
+        HistoryStatus {
+            undo_count: self.undo.len(),
+            redo_count: self.redo.len(),
+            next_undo: self.undo.back().map(|e| e.label.clone()),
+            next_redo: self.redo.last().map(|e| e.label.clone()),
+            used_bytes: self.used,
+            budget_bytes: self.budget,
+        }
+    }
+}
+
+// Rough heap footprint of a value, used to keep the journal within its memory budget
+pub fn approx_value_size(v: &Value) -> usize {
+    std::mem::size_of::<Value>() + match v {
+        Value::String(s) => s.len(),
+        Value::Array(a) => a.iter().map(approx_value_size).sum(),
+        Value::Object(m) => m.iter().map(|(k, v)| k.len() + approx_value_size(v) + 3 * std::mem::size_of::<usize>()).sum(),
+        _ => 0,
+    }
+}
+
+// Record an applied edit and mark the document dirty. Called by every mutating command.
+pub fn commit_edit(state: &AppState, label: &str, ops: Vec<EditOp>) {
+    state.history.lock().record(Edit::new(label, ops));
+    state.dirty.store(true, std::sync::atomic::Ordering::SeqCst);
+    state.invalidate_derived();
+}
+
+// Apply an op to the document, or its inverse when `undo`
+pub fn apply_op(root: &mut Value, op: &EditOp, undo: bool) -> Result<(), String> {
+    match (op, undo) {
+        (EditOp::Replace { pointer, old, new }, _) => {
+            *value_at_mut(root, pointer)? = if undo { old.clone() } else { new.clone() };
+        }
+        (EditOp::Add { pointer, index, value }, false) | (EditOp::Remove { pointer, index, value }, true) => {
+            insert_at_pointer(root, pointer, *index, value.clone())?;
+        }
+        (EditOp::Add { pointer, .. }, true) | (EditOp::Remove { pointer, .. }, false) => {
+            remove_at_pointer(root, pointer)?;
+        }
+    }
+    Ok(())
+}
+
+// Shared body of undo/redo: pop from one stack, apply, push onto the other
+fn step(state: &AppState, undo: bool) -> Result<Vec<Node>, String> {
+    let mut guard = state.doc_mut();
+    let Some(root_arc) = &mut *guard else { return Err(state.missing_document()); };
+    let mut history = state.history.lock();
+
+    let edit = if undo { history.undo.pop_back() } else { history.redo.pop() };
+    let Some(edit) = edit else {
+        return Err(if undo { "Nothing to undo".into() } else { "Nothing to redo".into() });
+    };
+
+    let root_mut: &mut Value = Arc::make_mut(root_arc);
+    let result = if undo {
+        edit.ops.iter().rev().try_for_each(|op| apply_op(root_mut, op, true))
+    } else {
+        edit.ops.iter().try_for_each(|op| apply_op(root_mut, op, false))
+    };
+    state.invalidate_derived();
+    if let Err(e) = result {
+        // The journal no longer matches the document; drop it rather than make things worse
+        history.clear();
+        history.incomplete = true;
+        return Err(e);
+    }
+
+    let mut affected: Vec<&str> = edit.ops.iter().map(EditOp::affected_pointer).collect();
+    affected.dedup();
+    let nodes = affected.into_iter()
+        .filter_map(|p| build_node_for_pointer(&*root_mut, p).ok())
+        .collect();
+    if undo {
+        history.redo.push(edit);
+    } else {
+        history.undo.push_back(edit);
+    }
+    state.dirty.store(true, std::sync::atomic::Ordering::SeqCst);
+    Ok(nodes)
+}
+
+#[tauri::command]
+pub fn undo(state: tauri::State<'_, AppState>) -> Result<Vec<Node>, String> {
+    step(&state, true)
+}
+
+#[tauri::command]
+pub fn redo(state: tauri::State<'_, AppState>) -> Result<Vec<Node>, String> {
+    step(&state, false)
+}
+
+#[tauri::command]
+pub fn history_status(state: tauri::State<'_, AppState>) -> HistoryStatus {
+    state.history.lock().status()
+}
+
+// Change the journal memory budget (bytes); oldest entries are dropped if it shrinks
+#[tauri::command]
+pub fn set_history_budget(bytes: usize, state: tauri::State<'_, AppState>) -> HistoryStatus {
+    let mut history = state.history.lock();
+    history.budget = bytes;
+    history.evict();
+    history.status()
+}
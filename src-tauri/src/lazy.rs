@@ -0,0 +1,470 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::sync::atomic::{AtomicBool, Ordering};
+use memmap2::Mmap;
+use serde_json::Value;
+use crate::types::Node;
+use crate::tree::{container_preview, escape_pointer_token, to_node_with_truncation, unescape_pointer_token};
+use crate::file::emit_progress;
+use crate::document::parse_index;
+
+// Containers spanning at least this many bytes get an index entry; smaller ones are rescanned
+// when needed, which keeps the index a small fraction of the file size
+const INDEX_MIN_SPAN: u64 = 4096;
+// Indexed arrays remember where every CHECKPOINT_EVERY-th element starts, so paging deep into a
+// huge array resumes near the page instead of rescanning from the opening bracket
+const CHECKPOINT_EVERY: u64 = 1024;
+// Indexing reports progress (and checks for cancellation) every this many bytes
+const PROGRESS_STEP: usize = 64 * 1024 * 1024;
+
+struct IndexEntry {
+    start: u64,    // offset of the opening bracket
+    end: u64,      // offset just past the closing bracket
+    children: u64, // members including repeated keys; only used for arrays
+    checkpoints: Vec<u64>, // arrays: offset of element (i + 1) * CHECKPOINT_EVERY at [i]
+}
+
+// A JSON file that is memory-mapped instead of parsed. A structural index records where large
+// containers end and how many children they have; everything else is read from the mapping on
+// demand, so only the nodes being looked at are ever materialized.
+pub struct LazyDocument {
+    mmap: Mmap,
+    root: usize,            // offset of the root value
+    index: Vec<IndexEntry>, // sorted by start
+}
+
+// One member of a container: object key (None for array elements) and the value's byte span
+struct Member {
+    key: Option<String>,
+    start: usize,
+    end: usize,
+}
+
+impl LazyDocument {
+    pub fn open(path: &str, cancel: &AtomicBool, app_handle: &tauri::AppHandle) -> Result<LazyDocument, String> {
+        let file = File::open(path).map_err(|e| e.to_string())?;
+        // Safety: the mapping is read-only; like any viewer of a memory-mapped file we rely on the
+        // file not being truncated by another process while it is open
+        let mmap = unsafe { Mmap::map(&file) }.map_err(|e| e.to_string())?;
+        let total = mmap.len() as u64;
+        let index = build_index(&mmap, |read| {
+            let canceled = cancel.load(Ordering::SeqCst);
+            emit_progress(app_handle, path, read as u64, total, false, canceled);
+            !canceled
+        })?;
+        emit_progress(app_handle, path, total, total, true, false);
+
+        let root = skip_ws(&mmap, skip_bom(&mmap));
+        if root >= mmap.len() {
+            return Err("File is empty".into());
+        }
+        Ok(LazyDocument { mmap, root, index })
+    }
+
+    fn bytes(&self) -> &[u8] {
+        &self.mmap
+    }
+
+    // End (exclusive) of the value starting at `pos`
+    fn value_end(&self, pos: usize) -> Result<usize, String> {
+        let b = self.bytes();
+        match b.get(pos) {
+            Some(b'"') => string_end(b, pos),
+            Some(b'{' | b'[') => match self.index.binary_search_by_key(&(pos as u64), |e| e.start) {
+                Ok(i) => Ok(self.index[i].end as usize),
+                Err(_) => container_end(b, pos),
+            },
+            Some(_) => Ok(pos + b[pos..].iter().position(|c| matches!(c, b',' | b'}' | b']') || c.is_ascii_whitespace()).unwrap_or(b.len() - pos)),
+            None => Err(format!("Unexpected end of file at offset {pos}")),
+        }
+    }
+
+    // Direct members of the container at `start`, in document order
+    fn members(&self, start: usize) -> Members<'_> {
+        Members { doc: self, pos: start + 1, is_object: self.bytes()[start] == b'{', done: false }
+    }
+
+    // Elements of the array at `start` beginning no later than element `from`: the index of the
+    // first element yielded, and the members from there on
+    fn elements_from(&self, start: usize, from: usize) -> (usize, Members<'_>) {
+        let checkpoint = match self.index.binary_search_by_key(&(start as u64), |e| e.start) {
+            Ok(i) => {
+                let checkpoints = &self.index[i].checkpoints;
+                let k = (from as u64 / CHECKPOINT_EVERY).min(checkpoints.len() as u64) as usize;
+                k.checked_sub(1).map(|k| (checkpoints[k] as usize, (k + 1) * CHECKPOINT_EVERY as usize))
+            }
+            Err(_) => None,
+        };
+        match checkpoint {
+            Some((pos, first)) => (first, Members { doc: self, pos, is_object: false, done: false }),
+            None => (0, self.members(start)),
+        }
+    }
+
+    // Members of the object at `start` as the parsed documents see them: each key once, at its
+    // first position, with the value of its last occurrence
+    fn object_members(&self, start: usize) -> Result<Vec<Member>, String> {
+        let mut members: Vec<Member> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for member in self.members(start) {
+            let member = member?;
+            let key = member.key.clone().unwrap_or_default();
+            match positions.get(&key) {
+                Some(&i) => members[i] = member,
+                None => {
+                    positions.insert(key, members.len());
+                    members.push(member);
+                }
+            }
+        }
+        Ok(members)
+    }
+
+    fn child_count(&self, start: usize) -> Result<usize, String> {
+        // Repeated keys collapse as in object_members, so objects count distinct keys
+        if self.bytes()[start] == b'{' {
+            let mut keys = HashSet::new();
+            for member in self.members(start) {
+                keys.insert(member?.key.unwrap_or_default());
+            }
+            return Ok(keys.len());
+        }
+        match self.index.binary_search_by_key(&(start as u64), |e| e.start) {
+            Ok(i) => Ok(self.index[i].children as usize),
+            Err(_) => self.members(start).try_fold(0, |n, m| m.map(|_| n + 1)),
+        }
+    }
+
+    fn node(&self, parent_ptr: &str, key: Option<&str>, start: usize, end: usize) -> Result<Node, String> {
+        let b = self.bytes();
+        if !matches!(b[start], b'{' | b'[') {
+            let value: Value = serde_json::from_slice(&b[start..end]).map_err(|e| format!("Invalid value at offset {start}: {e}"))?;
+            return Ok(to_node_with_truncation(parent_ptr, key, &value, None));
+        }
+        let is_object = b[start] == b'{';
+        let count = self.child_count(start)?;
+        Ok(Node {
+            pointer: match key {
+                Some(k) => format!("{}/{}", parent_ptr, escape_pointer_token(k)),
+                None => parent_ptr.to_string(),
+            },
+            key: key.map(str::to_string),
+            value_type: if is_object { "object".into() } else { "array".into() },
+            has_children: count > 0,
+            child_count: count,
+            preview: container_preview(is_object, count),
+            diff: None,
+            validation: None,
+        })
+    }
+
+    // Byte span of the value at a JSON Pointer. A repeated object key resolves to its last
+    // occurrence and array indices follow JSON Pointer syntax, as in the parsed documents.
+    fn locate(&self, pointer: &str) -> Result<(usize, usize), String> {
+        let mut span = (self.root, self.value_end(self.root)?);
+        if pointer.is_empty() {
+            return Ok(span);
+        }
+        for raw in pointer.strip_prefix('/').ok_or("Invalid pointer")?.split('/') {
+            let token = unescape_pointer_token(raw);
+            let b = self.bytes()[span.0];
+            let member = match b {
+                b'{' => {
+                    let mut found = None;
+                    for member in self.members(span.0) {
+                        let member = member?;
+                        if member.key.as_deref() == Some(token.as_str()) {
+                            found = Some(member);
+                        }
+                    }
+                    found
+                }
+                b'[' => {
+                    let index = parse_index(&token).ok_or("Invalid pointer")?;
+                    let (first, mut elements) = self.elements_from(span.0, index);
+                    elements.nth(index - first).transpose()?
+                }
+                _ => None,
+            };
+            let member = member.ok_or("Invalid pointer")?;
+            span = (member.start, member.end);
+        }
+        Ok(span)
+    }
+
+    // Heap held for the document: the structural index (mapped pages belong to the OS page cache)
+    pub fn heap_bytes(&self) -> usize {
+        self.index.capacity() * size_of::<IndexEntry>()
+            + self.index.iter().map(|e| e.checkpoints.capacity() * size_of::<u64>()).sum::<usize>()
+    }
+
+    // Same contract as tree::list_children. Objects are read whole so repeated keys collapse the
+    // way they do when parsing; arrays resume at a checkpoint.
+    pub fn list_children(&self, pointer: &str, offset: usize, limit: usize) -> Result<Vec<Node>, String> {
+        let (start, _) = self.locate(pointer)?;
+        match self.bytes()[start] {
+            b'{' => self.object_members(start)?
+                .into_iter()
+                .skip(offset)
+                .take(limit)
+                .map(|m| self.node(pointer, m.key.as_deref(), m.start, m.end))
+                .collect(),
+            b'[' => {
+                let (first, elements) = self.elements_from(start, offset);
+                elements
+                    .enumerate()
+                    .skip(offset - first)
+                    .take(limit)
+                    .map(|(i, member)| {
+                        let m = member?;
+                        self.node(pointer, Some(&(first + i).to_string()), m.start, m.end)
+                    })
+                    .collect()
+            }
+            _ => Ok(vec![]),
+        }
+    }
+
+    // Materialize the value at `pointer`
+    pub fn value_at(&self, pointer: &str) -> Result<Value, String> {
+        let (start, end) = self.locate(pointer)?;
+        serde_json::from_slice(&self.bytes()[start..end]).map_err(|e| format!("Invalid JSON at offset {start}: {e}"))
+    }
+}
+
+struct Members<'a> {
+    doc: &'a LazyDocument,
+    pos: usize, // just past the opening bracket or the previous member
+    is_object: bool,
+    done: bool,
+}
+
+impl Members<'_> {
+    fn next_member(&mut self) -> Result<Option<Member>, String> {
+        let b = self.doc.bytes();
+        let mut pos = skip_ws(b, self.pos);
+        match b.get(pos) {
+            Some(b'}' | b']') => return Ok(None),
+            Some(b',') => pos = skip_ws(b, pos + 1),
+            Some(_) => {}
+            None => return Err("Unexpected end of file".into()),
+        }
+        let key = if self.is_object {
+            let key_end = string_end(b, pos)?;
+            let key: String = serde_json::from_slice(&b[pos..key_end]).map_err(|e| format!("Invalid key at offset {pos}: {e}"))?;
+            pos = skip_ws(b, key_end);
+            if b.get(pos) != Some(&b':') {
+                return Err(format!("Expected ':' at offset {pos}"));
+            }
+            pos = skip_ws(b, pos + 1);
+            Some(key)
+        } else {
+            None
+        };
+        let end = self.doc.value_end(pos)?;
+        self.pos = end;
+        Ok(Some(Member { key, start: pos, end }))
+    }
+}
+
+impl Iterator for Members<'_> {
+    type Item = Result<Member, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_member().transpose();
+        if !matches!(next, Some(Ok(_))) {
+            self.done = true;
+        }
+        next
+    }
+}
+
+fn skip_bom(b: &[u8]) -> usize {
+    if b.starts_with(&[0xEF, 0xBB, 0xBF]) { 3 } else { 0 }
+}
+
+fn skip_ws(b: &[u8], mut pos: usize) -> usize {
+    while b.get(pos).is_some_and(u8::is_ascii_whitespace) {
+        pos += 1;
+    }
+    pos
+}
+
+// End (exclusive) of the string whose opening quote is at `pos`
+fn string_end(b: &[u8], pos: usize) -> Result<usize, String> {
+    if b.get(pos) != Some(&b'"') {
+        return Err(format!("Expected a string at offset {pos}"));
+    }
+    let mut i = pos + 1;
+    while i < b.len() {
+        match b[i] {
+            b'\\' => i += 2,
+            b'"' => return Ok(i + 1),
+            _ => i += 1,
+        }
+    }
+    Err(format!("Unterminated string at offset {pos}"))
+}
+
+// End (exclusive) of a small, unindexed container by scanning for its closing bracket
+fn container_end(b: &[u8], pos: usize) -> Result<usize, String> {
+    let mut depth = 0usize;
+    let mut i = pos;
+    while i < b.len() {
+        match b[i] {
+            b'"' => {
+                i = string_end(b, i)?;
+                continue;
+            }
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(i + 1);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    Err(format!("Unterminated container at offset {pos}"))
+}
+
+// One pass over the file matching brackets (outside strings) and counting direct children.
+// `progress` is called periodically with the bytes scanned and returns false to cancel.
+fn build_index(b: &[u8], mut progress: impl FnMut(usize) -> bool) -> Result<Vec<IndexEntry>, String> {
+    struct Open {
+        start: usize,
+        commas: u64,
+        nonempty: bool,
+        checkpoints: Vec<u64>,
+    }
+    let mut stack: Vec<Open> = Vec::new();
+    let mut index = Vec::new();
+    let mut root_done = false;
+    let mut next_report = PROGRESS_STEP;
+    let mut i = skip_bom(b);
+    while i < b.len() {
+        if i >= next_report {
+            if !progress(i) {
+                return Err("Parse canceled".into());
+            }
+            next_report += PROGRESS_STEP;
+        }
+        let c = b[i];
+        if c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        if root_done {
+            return Err(format!("Unexpected data after the root value at offset {i}"));
+        }
+        if let Some(top) = stack.last_mut() {
+            if !matches!(c, b'}' | b']' | b',' | b':') {
+                top.nonempty = true;
+            }
+        }
+        match c {
+            b'"' => {
+                i = string_end(b, i)?;
+                root_done = stack.is_empty();
+                continue;
+            }
+            b'{' | b'[' => stack.push(Open { start: i, commas: 0, nonempty: false, checkpoints: Vec::new() }),
+            b'}' | b']' => {
+                let open = stack.pop().ok_or_else(|| format!("Unmatched '{}' at offset {i}", c as char))?;
+                let expected = if b[open.start] == b'{' { b'}' } else { b']' };
+                if c != expected {
+                    return Err(format!("Mismatched '{}' at offset {i}", c as char));
+                }
+                let end = i + 1;
+                if (end - open.start) as u64 >= INDEX_MIN_SPAN {
+                    let children = if open.nonempty { open.commas + 1 } else { 0 };
+                    index.push(IndexEntry { start: open.start as u64, end: end as u64, children, checkpoints: open.checkpoints });
+                }
+                root_done = stack.is_empty();
+            }
+            b',' => {
+                if let Some(top) = stack.last_mut() {
+                    top.commas += 1;
+                    if top.commas % CHECKPOINT_EVERY == 0 && b[top.start] == b'[' {
+                        top.checkpoints.push(skip_ws(b, i + 1) as u64);
+                    }
+                }
+            }
+            _ => {
+                // Literal or number: consume it whole
+                while i + 1 < b.len() && !matches!(b[i + 1], b',' | b'}' | b']' | b':') && !b[i + 1].is_ascii_whitespace() {
+                    i += 1;
+                }
+                root_done = stack.is_empty();
+            }
+        }
+        i += 1;
+    }
+    if let Some(open) = stack.last() {
+        return Err(format!("Unterminated container at offset {}", open.start));
+    }
+    // Entries were recorded when containers closed; lookups need them ordered by start
+    index.sort_unstable_by_key(|e| e.start);
+    Ok(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn lazy(name: &str, json: &str) -> LazyDocument {
+        let path = std::env::temp_dir().join(format!("lazy-{name}-{}.json", std::process::id()));
+        File::create(&path).unwrap().write_all(json.as_bytes()).unwrap();
+        let file = File::open(&path).unwrap();
+        let mmap = unsafe { Mmap::map(&file) }.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let index = build_index(&mmap, |_| true).unwrap();
+        let root = skip_ws(&mmap, skip_bom(&mmap));
+        LazyDocument { mmap, root, index }
+    }
+
+    #[test]
+    fn repeated_keys_resolve_to_the_last_value() {
+        let doc = lazy("repeated", r#"{"a": 1, "b": {"c": true}, "a": [3]}"#);
+        assert_eq!(doc.value_at("/a").unwrap(), serde_json::json!([3]));
+        let children = doc.list_children("", 0, 10).unwrap();
+        let keys: Vec<_> = children.iter().map(|n| n.key.clone().unwrap()).collect();
+        assert_eq!(keys, ["a", "b"]);
+        assert_eq!(children[0].value_type, "array");
+
+        let nested = lazy("repeated-count", r#"{"o": {"k": 1, "k": 2, "j": 3}}"#);
+        let children = nested.list_children("", 0, 10).unwrap();
+        assert_eq!(children[0].child_count, 2);
+        assert_eq!(children[0].preview, container_preview(true, 2));
+    }
+
+    #[test]
+    fn array_tokens_follow_json_pointer_syntax() {
+        let doc = lazy("tokens", "[10, 11, 12]");
+        assert_eq!(doc.value_at("/1").unwrap(), serde_json::json!(11));
+        for token in ["/01", "/+1", "/-1", "/3"] {
+            assert!(doc.value_at(token).is_err(), "{token}");
+        }
+    }
+
+    #[test]
+    fn deep_pages_resume_at_checkpoints() {
+        let items: Vec<String> = (0..5000).map(|i| format!("{{\"n\": {i}}}")).collect();
+        let doc = lazy("pages", &format!("[\n  {}\n]", items.join(",\n  ")));
+        assert_eq!(doc.index.last().map(|e| e.checkpoints.len()), Some(4));
+        for offset in [0, 1023, 1024, 3000, 4096, 4998] {
+            let page = doc.list_children("", offset, 3).unwrap();
+            let keys: Vec<_> = page.iter().map(|n| n.key.clone().unwrap()).collect();
+            let expected: Vec<_> = (offset..(offset + 3).min(5000)).map(|i| i.to_string()).collect();
+            assert_eq!(keys, expected);
+            assert_eq!(doc.value_at(&format!("/{offset}/n")).unwrap(), serde_json::json!(offset));
+        }
+        assert!(doc.list_children("", 5000, 3).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,387 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use serde_json::Value;
+use tauri::async_runtime::spawn_blocking;
+use crate::state::AppState;
+use crate::tree::escape_pointer_token;
+use crate::types::{DuplicateKey, KeyOccurrence, LintReport};
+
+// Where a member's key was found, and the span of its value's text (up to the comma or brace
+// that ends the member, so it may carry trailing whitespace)
+struct Member {
+    offset: usize,
+    value_start: usize,
+    value_end: usize,
+}
+
+// A member's key is a range of its object's `keys` buffer
+type KeyedMember = (std::ops::Range<usize>, Member);
+
+enum Frame {
+    Array { pointer: String, index: usize },
+    // Keys are decoded into one buffer per object and compared when the object closes
+    Object { pointer: String, keys: Vec<u8>, members: Vec<KeyedMember>, pending: Option<KeyedMember> },
+}
+
+// Every object key that occurs more than once in `source`, which must hold valid JSON (scan it
+// after a successful parse). `root` is the pointer the source's top-level value has in the loaded
+// document. Returns up to `limit` duplicates in source order, each with the position and value of
+// every occurrence, and the total number found.
+pub fn duplicate_keys<R: Read + Seek>(mut source: R, root: &str, limit: usize) -> std::io::Result<(Vec<DuplicateKey>, usize)> {
+    let mut found = scan(BufReader::new(&mut source), root)?;
+    let total = found.len();
+    if found.is_empty() {
+        return Ok((Vec::new(), 0));
+    }
+    found.sort_by_key(|(_, _, members)| members[0].offset);
+    found.truncate(limit);
+
+    // Lines and columns are only counted for what is reported
+    let mut offsets: Vec<usize> = found.iter().flat_map(|(_, _, members)| members.iter().map(|m| m.offset)).collect();
+    offsets.sort_unstable();
+    source.seek(SeekFrom::Start(0))?;
+    let positions = line_columns(BufReader::new(&mut source), &offsets)?;
+
+    let mut duplicates = Vec::with_capacity(found.len());
+    for (pointer, key, members) in found {
+        let mut occurrences = Vec::with_capacity(members.len());
+        for m in members {
+            source.seek(SeekFrom::Start(m.value_start as u64))?;
+            let mut text = Vec::with_capacity(m.value_end - m.value_start);
+            (&mut source).take((m.value_end - m.value_start) as u64).read_to_end(&mut text)?;
+            let value = serde_json::from_slice(&text).unwrap_or(Value::Null);
+            let (line, column) = offsets.binary_search(&m.offset).ok().and_then(|i| positions.get(i).copied()).unwrap_or((0, 0));
+            occurrences.push(KeyOccurrence { offset: m.offset, line, column, value });
+        }
+        duplicates.push(DuplicateKey { pointer, key, occurrences });
+    }
+    Ok((duplicates, total))
+}
+
+// One pass over the source tracking open containers; returns (member pointer, key, every
+// occurrence) for each duplicated key
+fn scan<R: BufRead>(mut reader: R, root: &str) -> std::io::Result<Vec<(String, String, Vec<Member>)>> {
+    let mut found = Vec::new();
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut base = 0; // source offset of the current chunk
+    let mut in_string = false;
+    let mut escaped = false;
+    // Raw text of the key being read, once its opening quote has been seen at `key_at`
+    let mut key_raw = Vec::new();
+    let mut key_at: Option<usize> = None;
+    loop {
+        let chunk = reader.fill_buf()?;
+        if chunk.is_empty() {
+            break;
+        }
+        let len = chunk.len();
+        let mut i = 0;
+        while i < len {
+            if in_string {
+                if escaped {
+                    escaped = false;
+                    if key_at.is_some() {
+                        key_raw.push(chunk[i]);
+                    }
+                    i += 1;
+                    continue;
+                }
+                // Skip to the next quote or backslash
+                let run = chunk[i..].iter().position(|&b| b == b'"' || b == b'\\').unwrap_or(len - i);
+                if key_at.is_some() {
+                    key_raw.extend_from_slice(&chunk[i..i + run]);
+                }
+                i += run;
+                if i == len {
+                    break;
+                }
+                if chunk[i] == b'\\' {
+                    escaped = true;
+                    if key_at.is_some() {
+                        key_raw.push(b'\\');
+                    }
+                } else {
+                    in_string = false;
+                    if let (Some(offset), Some(Frame::Object { keys, pending, .. })) = (key_at.take(), stack.last_mut()) {
+                        let start = keys.len();
+                        decode_key(&key_raw, keys);
+                        *pending = Some((start..keys.len(), Member { offset, value_start: 0, value_end: 0 }));
+                    }
+                }
+                i += 1;
+                continue;
+            }
+            let pos = base + i;
+            match chunk[i] {
+                b'"' => {
+                    in_string = true;
+                    if let Some(Frame::Object { pending: None, .. }) = stack.last() {
+                        key_raw.clear();
+                        key_at = Some(pos);
+                    }
+                }
+                b @ (b'{' | b'[') => {
+                    let pointer = match stack.last() {
+                        None => root.to_string(),
+                        Some(Frame::Array { pointer, index }) => format!("{pointer}/{index}"),
+                        Some(Frame::Object { pointer, keys, pending, .. }) => {
+                            let key = pending.as_ref().map_or("", |(range, _)| key_str(keys, range));
+                            format!("{pointer}/{}", escape_pointer_token(key))
+                        }
+                    };
+                    stack.push(if b == b'{' {
+                        Frame::Object { pointer, keys: Vec::new(), members: Vec::new(), pending: None }
+                    } else {
+                        Frame::Array { pointer, index: 0 }
+                    });
+                }
+                b':' => {
+                    if let Some(Frame::Object { pending: Some((_, member)), .. }) = stack.last_mut() {
+                        member.value_start = pos + 1;
+                    }
+                }
+                b',' => match stack.last_mut() {
+                    Some(Frame::Array { index, .. }) => *index += 1,
+                    Some(Frame::Object { members, pending, .. }) => end_member(members, pending, pos),
+                    None => {}
+                },
+                b'}' => {
+                    if let Some(Frame::Object { pointer, keys, mut members, mut pending }) = stack.pop() {
+                        end_member(&mut members, &mut pending, pos);
+                        collect_duplicates(&pointer, &keys, members, &mut found);
+                    }
+                }
+                b']' => {
+                    stack.pop();
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        base += len;
+        reader.consume(len);
+    }
+    Ok(found)
+}
+
+// 1-based line and character column of each of the sorted `offsets`
+fn line_columns<R: BufRead>(mut reader: R, offsets: &[usize]) -> std::io::Result<Vec<(usize, usize)>> {
+    let mut positions = Vec::with_capacity(offsets.len());
+    let (mut pos, mut line, mut column) = (0, 1, 1);
+    while positions.len() < offsets.len() {
+        let chunk = reader.fill_buf()?;
+        if chunk.is_empty() {
+            break;
+        }
+        let len = chunk.len();
+        for &b in chunk {
+            while offsets.get(positions.len()) == Some(&pos) {
+                positions.push((line, column));
+            }
+            if b == b'\n' {
+                line += 1;
+                column = 1;
+            } else if b & 0xC0 != 0x80 {
+                column += 1;
+            }
+            pos += 1;
+        }
+        reader.consume(len);
+    }
+    Ok(positions)
+}
+
+fn end_member(members: &mut Vec<KeyedMember>, pending: &mut Option<KeyedMember>, pos: usize) {
+    if let Some((key, mut member)) = pending.take() {
+        member.value_end = pos;
+        members.push((key, member));
+    }
+}
+
+// Group a closed object's members by key; keys seen more than once are duplicates
+fn collect_duplicates(pointer: &str, keys: &[u8], members: Vec<KeyedMember>, found: &mut Vec<(String, String, Vec<Member>)>) {
+    if members.len() < 2 {
+        return;
+    }
+    // Stable, so each group stays in source order
+    let key = |i: usize| &keys[members[i].0.clone()];
+    let mut order: Vec<usize> = (0..members.len()).collect();
+    order.sort_by(|&a, &b| key(a).cmp(key(b)));
+    let groups: Vec<Vec<usize>> = order.chunk_by(|&a, &b| key(a) == key(b)).filter(|g| g.len() > 1).map(<[usize]>::to_vec).collect();
+    if groups.is_empty() {
+        return;
+    }
+    let mut members: Vec<Option<KeyedMember>> = members.into_iter().map(Some).collect();
+    for group in groups {
+        let occurrences: Vec<KeyedMember> = group.iter().filter_map(|&i| members[i].take()).collect();
+        let key = key_str(keys, &occurrences[0].0).to_string();
+        found.push((format!("{pointer}/{}", escape_pointer_token(&key)), key, occurrences.into_iter().map(|(_, m)| m).collect()));
+    }
+}
+
+fn key_str<'k>(keys: &'k [u8], range: &std::ops::Range<usize>) -> &'k str {
+    std::str::from_utf8(&keys[range.clone()]).unwrap_or("")
+}
+
+// Decode a key's raw text (between the quotes) onto `out`, resolving escapes
+fn decode_key(raw: &[u8], out: &mut Vec<u8>) {
+    if !raw.contains(&b'\\') {
+        out.extend_from_slice(raw);
+        return;
+    }
+    let mut quoted = Vec::with_capacity(raw.len() + 2);
+    quoted.push(b'"');
+    quoted.extend_from_slice(raw);
+    quoted.push(b'"');
+    match serde_json::from_slice::<String>(&quoted) {
+        Ok(key) => out.extend_from_slice(key.as_bytes()),
+        Err(_) => out.extend_from_slice(raw),
+    }
+}
+
+// Duplicate keys in the file at `path`. NDJSON is scanned a line at a time, with pointers into
+// the virtual root array; lines that are not valid JSON are skipped as they are when loading.
+pub fn file_duplicate_keys(path: &str, ndjson: bool, limit: usize) -> std::io::Result<(Vec<DuplicateKey>, usize)> {
+    let file = File::open(path)?;
+    if !ndjson {
+        return duplicate_keys(file, "", limit);
+    }
+    let mut reader = BufReader::new(file);
+    let (mut duplicates, mut total) = (Vec::new(), 0);
+    let (mut item, mut line_no, mut line_start) = (0, 0, 0);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        let n = reader.read_until(b'\n', &mut buf)?;
+        if n == 0 {
+            break;
+        }
+        line_no += 1;
+        let valid = !buf.iter().all(u8::is_ascii_whitespace) && serde_json::from_slice::<serde::de::IgnoredAny>(&buf).is_ok();
+        if valid {
+            let (found, count) = duplicate_keys(std::io::Cursor::new(&buf), &format!("/{item}"), limit - duplicates.len())?;
+            duplicates.extend(found.into_iter().map(|mut d| {
+                for o in &mut d.occurrences {
+                    o.offset += line_start;
+                    o.line += line_no - 1;
+                }
+                d
+            }));
+            total += count;
+            item += 1;
+        }
+        line_start += n;
+    }
+    Ok((duplicates, total))
+}
+
+// Lint the loaded document's source: every duplicated object key with the position and value of
+// each occurrence. Files are scanned again, so the report is complete (DocumentInfo keeps a capped
+// list) and covers lazily loaded files too; clipboard documents report what was found on load.
+#[tauri::command]
+pub async fn lint_document(state: tauri::State<'_, AppState>) -> Result<LintReport, String> {
+    if state.main_document().is_err() && state.lazy_doc.read().is_none() {
+        return Err(state.missing_document());
+    }
+    let info = state.doc_info.read().clone();
+    if info.lenient || info.repair_count > 0 {
+        return Err("Duplicate keys can only be checked in a valid JSON source".into());
+    }
+    let Some(path) = info.path else {
+        return Ok(LintReport { path: None, duplicate_keys: info.duplicate_keys });
+    };
+    let ndjson = info.format == "ndjson";
+    let scan_path = path.clone();
+    let (duplicate_keys, _) = spawn_blocking(move || file_duplicate_keys(&scan_path, ndjson, usize::MAX))
+        .await
+        .map_err(|e| format!("Join error: {e}"))?
+        .map_err(|e| format!("Failed to read {path}: {e}"))?;
+    Ok(LintReport { path: Some(path), duplicate_keys })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Cursor;
+
+    // (offset, line, column, value) of each occurrence
+    type Occurrences = Vec<(usize, usize, usize, Value)>;
+    // (pointer, key, occurrences) of each duplicate
+    type Found = Vec<(String, String, Occurrences)>;
+
+    fn flatten(duplicates: Vec<DuplicateKey>) -> Found {
+        duplicates
+            .into_iter()
+            .map(|d| (d.pointer, d.key, d.occurrences.into_iter().map(|o| (o.offset, o.line, o.column, o.value)).collect()))
+            .collect()
+    }
+
+    fn scan_text(src: &str, limit: usize) -> (Found, usize) {
+        let (duplicates, total) = duplicate_keys(Cursor::new(src.as_bytes()), "", limit).unwrap();
+        (flatten(duplicates), total)
+    }
+
+    fn dup(pointer: &str, key: &str, occurrences: &[(usize, usize, usize, Value)]) -> (String, String, Occurrences) {
+        (pointer.to_string(), key.to_string(), occurrences.to_vec())
+    }
+
+    #[test]
+    fn nested_duplicates() {
+        let src = "{\n  \"a\": 1,\n  \"b\": {\"x\": [1,2], \"y\": 0, \"x\": {\"k\": true, \"k\": false}},\n  \"a\": \"second\"\n}\n";
+        let (found, total) = scan_text(src, usize::MAX);
+        assert_eq!(total, 3);
+        assert_eq!(found, vec![
+            dup("/a", "a", &[(4, 2, 3, json!(1)), (73, 4, 3, json!("second"))]),
+            dup("/b/x", "x", &[(20, 3, 9, json!([1, 2])), (40, 3, 29, json!({"k": false}))]),
+            dup("/b/x/k", "k", &[(46, 3, 35, json!(true)), (57, 3, 46, json!(false))]),
+        ]);
+
+        let (found, total) = scan_text(src, 1);
+        assert_eq!((found.len(), total), (1, 3));
+        assert!(scan_text(r#"{"a": {"a": 1}, "b": [{"a": 1}, {"a": 2}]}"#, usize::MAX).0.is_empty());
+    }
+
+    #[test]
+    fn escaped_keys_compare_decoded() {
+        let src = r#"{"caf\u00e9": 1, "café": 2, "t\"q": 3, "t\u0022q": 4, "a/b~c": [{"n": 1, "n": 2}]}"#;
+        let (found, _) = scan_text(src, usize::MAX);
+        assert_eq!(found, vec![
+            dup("/café", "café", &[(1, 1, 2, json!(1)), (17, 1, 18, json!(2))]),
+            dup("/t\"q", "t\"q", &[(29, 1, 29, json!(3)), (40, 1, 40, json!(4))]),
+            dup("/a~1b~0c/0/n", "n", &[(66, 1, 66, json!(1)), (74, 1, 74, json!(2))]),
+        ]);
+    }
+
+    #[test]
+    fn keys_and_strings_across_read_buffers() {
+        // Long strings full of escapes push the keys over BufReader's 8 KiB chunks
+        let filler = "ab\\\"cd\\\\".repeat(1200);
+        let src = format!(r#"{{"s": "{filler}", "long_key_{filler}": 1, "d": "{filler}", "long_key_{filler}": 2, "d": 3}}"#);
+        let key = format!("long_key_{}", "ab\"cd\\".repeat(1200));
+        let first = src.find("\"long_key_").unwrap();
+        let second = src.rfind("\"long_key_").unwrap();
+        let (d1, d2) = (src.find("\"d\"").unwrap(), src.rfind("\"d\"").unwrap());
+        let (found, _) = scan_text(&src, usize::MAX);
+        assert_eq!(found, vec![
+            dup(&format!("/{key}"), &key, &[(first, 1, first + 1, json!(1)), (second, 1, second + 1, json!(2))]),
+            dup("/d", "d", &[(d1, 1, d1 + 1, json!(filler.replace("\\\"", "\"").replace("\\\\", "\\"))), (d2, 1, d2 + 1, json!(3))]),
+        ]);
+    }
+
+    #[test]
+    fn ndjson_lines() {
+        let src = "{\"id\": 1, \"id\": 2}\n\n{\"ok\": true}\nnot json\n{\"v\": {\"w\": 1, \"w\": 2}}\n";
+        let path = std::env::temp_dir().join(format!("lint-ndjson-{}.jsonl", std::process::id()));
+        std::fs::write(&path, src).unwrap();
+        let result = file_duplicate_keys(path.to_str().unwrap(), true, usize::MAX);
+        std::fs::remove_file(&path).unwrap();
+        let (duplicates, total) = result.unwrap();
+        assert_eq!(total, 2);
+        // Pointers count only the lines that parse, like the loaded root array
+        assert_eq!(flatten(duplicates), vec![
+            dup("/0/id", "id", &[(1, 1, 2, json!(1)), (10, 1, 11, json!(2))]),
+            dup("/2/v/w", "w", &[(49, 5, 8, json!(1)), (57, 5, 16, json!(2))]),
+        ]);
+    }
+}
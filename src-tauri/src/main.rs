@@ -9,14 +9,36 @@ mod file;
 mod search;
 mod node;
 mod config;
+mod history;
+mod query;
+mod filter;
+mod replace;
+mod diff;
+mod patch;
+mod schema;
+mod codegen;
+mod lazy;
+mod document;
+mod tape;
+mod parse_error;
+mod recover;
+mod lint;
 
 // Import the app state
 use crate::state::AppState;
 
 // Import command functions from modules
-use file::{open_file, open_clipboard, cancel_parse, load_children, open_file_dialog};
-use search::{search, search_stream};
-use node::{get_node_value, copy_node_value, set_node_value, set_subtree, parse_stringified_json};
+use file::{open_file, open_clipboard, cancel_parse, load_children, open_file_dialog, get_document_info, get_memory_usage, is_dirty, save_file, save_file_as, save_file_dialog};
+use search::{search, search_stream, cancel_search};
+use query::{query_jsonpath, run_jq};
+use replace::replace_all;
+use diff::{open_compare_file, close_compare, compute_diff, get_diff};
+use patch::{export_patch, apply_patch};
+use schema::{validate_against_schema, infer_schema};
+use codegen::generate_types;
+use lint::lint_document;
+use node::{get_node_value, copy_node_value, set_node_value, set_subtree, set_node_json, parse_stringified_json, insert_node, delete_node, rename_key, duplicate_node, move_node};
+use history::{undo, redo, history_status, set_history_budget};
 use config::{save_last_opened_file, load_last_opened_file, clear_last_opened_file};
 
 pub fn main() {
@@ -29,6 +51,20 @@ pub fn main() {
             load_children, 
             search,
             search_stream,
+            cancel_search,
+            replace_all,
+            open_compare_file,
+            close_compare,
+            compute_diff,
+            get_diff,
+            export_patch,
+            apply_patch,
+            validate_against_schema,
+            infer_schema,
+            generate_types,
+            lint_document,
+            query_jsonpath,
+            run_jq,
             cancel_parse,
             save_last_opened_file,
             load_last_opened_file,
@@ -39,7 +75,23 @@ pub fn main() {
             open_clipboard,
             set_node_value,
             set_subtree,
-            open_file_dialog
+            set_node_json,
+            insert_node,
+            delete_node,
+            rename_key,
+            duplicate_node,
+            move_node,
+            open_file_dialog,
+            get_document_info,
+            get_memory_usage,
+            is_dirty,
+            save_file,
+            save_file_as,
+            save_file_dialog,
+            undo,
+            redo,
+            history_status,
+            set_history_budget
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
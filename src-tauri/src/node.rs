@@ -1,20 +1,14 @@
 use std::sync::Arc;
-use crate::state::AppState;
-use crate::types::Node;
-use crate::tree::build_node_for_pointer;
+use crate::state::{AppState, MainDocument};
+use crate::document::{parse_index, JsonNode};
+use serde_json::Value;
+use crate::types::{MoveResult, Node, ValueChange};
+use crate::tree::{build_node_for_pointer, create_node_for_path, value_type_name, child_pointer, insert_at_pointer, list_children, remove_at_pointer, split_pointer, value_at_mut};
+use crate::history::{commit_edit, EditOp};
 
 #[tauri::command]
 pub fn get_node_value(pointer: String, state: tauri::State<'_, AppState>) -> Result<String, String> {
-    let guard = state.doc.read();
-    let Some(root) = &*guard else { return Err("No document loaded".into()); };
-    
-    let value = if pointer.is_empty() {
-        root.as_ref()
-    } else {
-        root.pointer(&pointer).ok_or("Invalid pointer")?
-    };
-    
-    serde_json::to_string(value).map_err(|e| e.to_string())
+    node_json(&pointer, false, &state)
 }
 
 // Copy the full JSON value of a node (or root if pointer empty) directly to the system clipboard.
@@ -23,15 +17,7 @@ pub fn get_node_value(pointer: String, state: tauri::State<'_, AppState>) -> Res
 #[tauri::command]
 pub fn copy_node_value(pointer: String, state: tauri::State<'_, AppState>) -> Result<(), String> {
     use arboard::Clipboard;
-    let guard = state.doc.read();
-    let Some(root) = &*guard else { return Err("No document loaded".into()); };
-
-    let value = if pointer.is_empty() { 
-        root.as_ref() 
-    } else { 
-        root.pointer(&pointer).ok_or("Invalid pointer")? 
-    };
-    let serialized = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    let serialized = node_json(&pointer, true, &state)?;
     let mut cb = Clipboard::new().map_err(|e| e.to_string())?;
     cb.set_text(serialized).map_err(|e| e.to_string())?;
     Ok(())
@@ -41,8 +27,8 @@ pub fn copy_node_value(pointer: String, state: tauri::State<'_, AppState>) -> Re
 pub fn set_node_value(pointer: String, new_value: String, state: tauri::State<'_, AppState>) -> Result<Node, String> {
     use serde_json::Value as JsonValue;
     // Acquire write lock to allow mutation
-    let mut guard = state.doc.write();
-    let Some(root_arc) = &mut *guard else { return Err("No document loaded".into()); };
+    let mut guard = state.doc_mut();
+    let Some(root_arc) = &mut *guard else { return Err(state.missing_document()); };
 
     // We clone the Arc if needed to obtain a mutable reference
     let root_mut: &mut JsonValue = Arc::make_mut(root_arc);
@@ -56,6 +42,7 @@ pub fn set_node_value(pointer: String, new_value: String, state: tauri::State<'_
     let current_ptr = current_value_opt.ok_or("Invalid pointer")?;
     // Safety: we only use pointer while holding &mut root_mut
     let current_value: &mut JsonValue = unsafe { &mut *current_ptr };
+    let old_value = current_value.clone();
 
     // Only allow editing primitive scalar types
     match current_value {
@@ -65,15 +52,9 @@ pub fn set_node_value(pointer: String, new_value: String, state: tauri::State<'_
         }
         JsonValue::Number(n) => {
             // Parse number; must remain number
-            // Accept integer or float
+            // Numbers keep their literal text, so 1.10 or a 20-digit id is stored exactly as typed
             let trimmed = new_value.trim();
-            let parsed_number = if let Ok(i) = trimmed.parse::<i64>() { 
-                serde_json::Number::from(i) 
-            } else if let Ok(f) = trimmed.parse::<f64>() { 
-                serde_json::Number::from_f64(f).ok_or("Invalid number")? 
-            } else { 
-                return Err("Invalid number literal".into()); 
-            };
+            let parsed_number = trimmed.parse::<serde_json::Number>().map_err(|_| "Invalid number literal")?;
             *n = parsed_number;
         }
         JsonValue::Bool(b) => {
@@ -93,8 +74,11 @@ pub fn set_node_value(pointer: String, new_value: String, state: tauri::State<'_
         }
     }
 
+    let new_value = current_value.clone();
+    commit_edit(&state, "Edit value", vec![EditOp::Replace { pointer: pointer.clone(), old: old_value, new: new_value }]);
+
     // Build updated node to return
-    build_node_for_pointer(root_mut, &pointer)
+    build_node_for_pointer(&*root_mut, &pointer)
 }
 
 #[tauri::command]
@@ -111,8 +95,8 @@ pub fn set_subtree(pointer: String, new_json: String, state: tauri::State<'_, Ap
     };
 
     // Acquire write lock
-    let mut guard = state.doc.write();
-    let Some(root_arc) = &mut *guard else { return Err("No document loaded".into()); };
+    let mut guard = state.doc_mut();
+    let Some(root_arc) = &mut *guard else { return Err(state.missing_document()); };
     let root_mut: &mut JsonValue = Arc::make_mut(root_arc);
 
     // Locate current value
@@ -135,9 +119,70 @@ pub fn set_subtree(pointer: String, new_json: String, state: tauri::State<'_, Ap
     }
 
     // Replace
-    *current = parsed;
+    let new_value = parsed.clone();
+    let old_value = std::mem::replace(current, parsed);
+    commit_edit(&state, "Edit subtree", vec![EditOp::Replace { pointer: pointer.clone(), old: old_value, new: new_value }]);
+
+    build_node_for_pointer(&*root_mut, &pointer)
+}
+
+// Replace the value at `pointer` with any JSON literal, regardless of the current type (including
+// null). With `dry_run` nothing is changed and the result only reports old vs. new type so the
+// UI can ask for confirmation before a type-changing edit.
+#[tauri::command]
+pub fn set_node_json(pointer: String, new_json: String, dry_run: Option<bool>, state: tauri::State<'_, AppState>) -> Result<ValueChange, String> {
+    let parsed: Value = serde_json::from_str(&new_json).map_err(|e| format!("Parse error: {e}"))?;
+    let new_type = value_type_name(&parsed).to_string();
+
+    if dry_run.unwrap_or(false) {
+        let old_type = match state.main_document()? {
+            MainDocument::Tree(doc) => doc.pointer(&pointer).map(value_type_name),
+            MainDocument::Compact(doc) => doc.root().pointer(&pointer).map(value_type_name),
+        }.ok_or("Invalid pointer")?.to_string();
+        return Ok(ValueChange {
+            node: create_node_for_path(&parsed, &pointer),
+            type_changed: old_type != new_type,
+            pointer,
+            old_type,
+            new_type,
+            applied: false,
+        });
+    }
+
+    let mut guard = state.doc_mut();
+    let Some(root_arc) = &mut *guard else { return Err(state.missing_document()); };
+    let root_mut: &mut Value = Arc::make_mut(root_arc);
+
+    let current = value_at_mut(root_mut, &pointer)?;
+    let old_value = std::mem::replace(current, parsed.clone());
+    let old_type = value_type_name(&old_value).to_string();
+    commit_edit(&state, "Set JSON value", vec![EditOp::Replace { pointer: pointer.clone(), old: old_value, new: parsed }]);
+
+    Ok(ValueChange {
+        node: build_node_for_pointer(&*root_mut, &pointer)?,
+        type_changed: old_type != new_type,
+        pointer,
+        old_type,
+        new_type,
+        applied: true,
+    })
+}
+
+// JSON text of the node at `pointer` in the main document, whichever way it is loaded
+fn node_json(pointer: &str, pretty: bool, state: &AppState) -> Result<String, String> {
+    if let Some(lazy) = state.lazy_doc.read().clone() {
+        return serialize_node(&lazy.value_at(pointer)?, "", pretty);
+    }
+    match state.main_document()? {
+        MainDocument::Tree(doc) => serialize_node(doc.as_ref(), pointer, pretty),
+        MainDocument::Compact(doc) => serialize_node(doc.root(), pointer, pretty),
+    }
+}
 
-    build_node_for_pointer(root_mut, &pointer)
+fn serialize_node<'a>(root: impl JsonNode<'a>, pointer: &str, pretty: bool) -> Result<String, String> {
+    let value = root.pointer(pointer).ok_or("Invalid pointer")?;
+    let text = if pretty { serde_json::to_string_pretty(&value) } else { serde_json::to_string(&value) };
+    text.map_err(|e| e.to_string())
 }
 
 // Attempt to parse a string node whose content itself is JSON (object/array) and replace it in-place.
@@ -148,8 +193,8 @@ pub fn set_subtree(pointer: String, new_json: String, state: tauri::State<'_, Ap
 pub fn parse_stringified_json(pointer: String, state: tauri::State<'_, AppState>) -> Result<Node, String> {
     use serde_json::Value as JsonValue;
     // Acquire write lock for mutation
-    let mut guard = state.doc.write();
-    let Some(root_arc) = &mut *guard else { return Err("No document loaded".into()); };
+    let mut guard = state.doc_mut();
+    let Some(root_arc) = &mut *guard else { return Err(state.missing_document()); };
     let root_mut: &mut JsonValue = Arc::make_mut(root_arc);
 
     // Locate target node (must be string)
@@ -173,12 +218,209 @@ pub fn parse_stringified_json(pointer: String, state: tauri::State<'_, AppState>
     }
 
     let parsed: JsonValue = serde_json::from_str(trimmed).map_err(|e| format!("Parse error: {e}"))?;
-    match parsed {
+    let old_value = match parsed {
         JsonValue::Object(_) | JsonValue::Array(_) => {
-            *current = parsed; // replace
+            std::mem::replace(current, parsed.clone()) // replace
         }
         _ => return Err("Parsed value is not an object/array".into()),
+    };
+    commit_edit(&state, "Parse stringified JSON", vec![EditOp::Replace { pointer: pointer.clone(), old: old_value, new: parsed }]);
+
+    build_node_for_pointer(&*root_mut, &pointer)
+}
+
+// Structural edits. Each records a single undoable edit and returns the first page of the
+// affected parent's children (same shape as load_children) so the UI can refresh in place.
+
+const REFRESH_LIMIT: usize = 100;
+
+// Insert a new key (objects) or element (arrays) under `parent_pointer`. `index` is the position
+// among the parent's children and defaults to the end; `key` is required for objects.
+#[tauri::command]
+pub fn insert_node(parent_pointer: String, key: Option<String>, index: Option<usize>, value_json: String, state: tauri::State<'_, AppState>) -> Result<Vec<Node>, String> {
+    let value: Value = serde_json::from_str(&value_json).map_err(|e| format!("Parse error: {e}"))?;
+
+    let mut guard = state.doc_mut();
+    let Some(root_arc) = &mut *guard else { return Err(state.missing_document()); };
+    let root_mut: &mut Value = Arc::make_mut(root_arc);
+
+    let (pointer, index) = match value_at_mut(root_mut, &parent_pointer)? {
+        Value::Object(map) => {
+            let key = key.ok_or("Key is required when inserting into an object")?;
+            let index = index.unwrap_or(map.len()).min(map.len());
+            (child_pointer(&parent_pointer, &key), index)
+        }
+        Value::Array(arr) => {
+            let index = index.unwrap_or(arr.len());
+            if index > arr.len() {
+                return Err(format!("Array index out of bounds: {index}"));
+            }
+            (child_pointer(&parent_pointer, &index.to_string()), index)
+        }
+        _ => return Err("Parent is not an object or array".into()),
+    };
+
+    insert_at_pointer(root_mut, &pointer, index, value.clone())?;
+    commit_edit(&state, "Insert node", vec![EditOp::Add { pointer, index, value }]);
+    Ok(list_children(&*root_mut, &parent_pointer, 0, REFRESH_LIMIT))
+}
+
+#[tauri::command]
+pub fn delete_node(pointer: String, state: tauri::State<'_, AppState>) -> Result<Vec<Node>, String> {
+    let mut guard = state.doc_mut();
+    let Some(root_arc) = &mut *guard else { return Err(state.missing_document()); };
+    let root_mut: &mut Value = Arc::make_mut(root_arc);
+
+    let (parent_pointer, _) = split_pointer(&pointer).ok_or("Cannot delete the document root")?;
+    let parent_pointer = parent_pointer.to_string();
+    let (index, value) = remove_at_pointer(root_mut, &pointer)?;
+    commit_edit(&state, "Delete node", vec![EditOp::Remove { pointer, index, value }]);
+    Ok(list_children(&*root_mut, &parent_pointer, 0, REFRESH_LIMIT))
+}
+
+// Rename an object key, keeping it at the same position among its siblings
+#[tauri::command]
+pub fn rename_key(pointer: String, new_key: String, state: tauri::State<'_, AppState>) -> Result<Vec<Node>, String> {
+    let mut guard = state.doc_mut();
+    let Some(root_arc) = &mut *guard else { return Err(state.missing_document()); };
+    let root_mut: &mut Value = Arc::make_mut(root_arc);
+
+    let (parent_pointer, old_key) = split_pointer(&pointer).ok_or("Cannot rename the document root")?;
+    let parent_pointer = parent_pointer.to_string();
+    let Some(parent) = value_at_mut(root_mut, &parent_pointer)?.as_object() else {
+        return Err("Only object keys can be renamed".into());
+    };
+    if old_key == new_key {
+        return Ok(list_children(&*root_mut, &parent_pointer, 0, REFRESH_LIMIT));
+    }
+    if parent.contains_key(&new_key) {
+        return Err(format!("Key already exists: {new_key}"));
+    }
+
+    let new_pointer = child_pointer(&parent_pointer, &new_key);
+    let (index, value) = remove_at_pointer(root_mut, &pointer)?;
+    insert_at_pointer(root_mut, &new_pointer, index, value.clone())?;
+    commit_edit(&state, "Rename key", vec![
+        EditOp::Remove { pointer, index, value: value.clone() },
+        EditOp::Add { pointer: new_pointer, index, value },
+    ]);
+    Ok(list_children(&*root_mut, &parent_pointer, 0, REFRESH_LIMIT))
+}
+
+// Insert a copy of a node right after the original. Object copies get `new_key`, or
+// "<key> copy" (numbered if taken) when omitted.
+#[tauri::command]
+pub fn duplicate_node(pointer: String, new_key: Option<String>, state: tauri::State<'_, AppState>) -> Result<Vec<Node>, String> {
+    let mut guard = state.doc_mut();
+    let Some(root_arc) = &mut *guard else { return Err(state.missing_document()); };
+    let root_mut: &mut Value = Arc::make_mut(root_arc);
+
+    let (parent_pointer, key) = split_pointer(&pointer).ok_or("Cannot duplicate the document root")?;
+    let parent_pointer = parent_pointer.to_string();
+    let (copy_pointer, index, value) = match value_at_mut(root_mut, &parent_pointer)? {
+        Value::Object(map) => {
+            let index = map.keys().position(|k| *k == key).ok_or("Invalid pointer")? + 1;
+            let value = map.get(&key).cloned().ok_or("Invalid pointer")?;
+            let copy_key = match new_key {
+                Some(k) if map.contains_key(&k) => return Err(format!("Key already exists: {k}")),
+                Some(k) => k,
+                None => {
+                    let mut candidate = format!("{key} copy");
+                    let mut n = 2;
+                    while map.contains_key(&candidate) {
+                        candidate = format!("{key} copy {n}");
+                        n += 1;
+                    }
+                    candidate
+                }
+            };
+            (child_pointer(&parent_pointer, &copy_key), index, value)
+        }
+        Value::Array(arr) => {
+            let i: usize = key.parse().map_err(|_| "Invalid pointer")?;
+            let value = arr.get(i).cloned().ok_or("Invalid pointer")?;
+            (child_pointer(&parent_pointer, &(i + 1).to_string()), i + 1, value)
+        }
+        _ => return Err("Invalid pointer".into()),
+    };
+
+    insert_at_pointer(root_mut, &copy_pointer, index, value.clone())?;
+    commit_edit(&state, "Duplicate node", vec![EditOp::Add { pointer: copy_pointer, index, value }]);
+    Ok(list_children(&*root_mut, &parent_pointer, 0, REFRESH_LIMIT))
+}
+
+// Move a node under another parent. Like JSON Patch "move", the target is resolved after the
+// node has been removed from its old location. `key` names it in an object target (defaults to
+// the current key); `index` positions it (defaults to the end). Returns the children of both the
+// parent the node left and the target.
+#[tauri::command]
+pub fn move_node(pointer: String, target_parent: String, key: Option<String>, index: Option<usize>, state: tauri::State<'_, AppState>) -> Result<MoveResult, String> {
+    let mut guard = state.doc_mut();
+    let Some(root_arc) = &mut *guard else { return Err(state.missing_document()); };
+    let root_mut: &mut Value = Arc::make_mut(root_arc);
+
+    let (source_parent, old_key) = split_pointer(&pointer).ok_or("Cannot move the document root")?;
+    let source_parent = source_parent.to_string();
+    if target_parent == pointer || target_parent.starts_with(&format!("{pointer}/")) {
+        return Err("Cannot move a node into itself".into());
     }
 
-    build_node_for_pointer(root_mut, &pointer)
+    let (from_index, value) = remove_at_pointer(root_mut, &pointer)?;
+    let mut target_is_array = false;
+    let target = match value_at_mut(root_mut, &target_parent) {
+        Ok(Value::Object(map)) => {
+            let key = key.unwrap_or(old_key);
+            if map.contains_key(&key) {
+                Err(format!("Key already exists: {key}"))
+            } else {
+                let to_index = index.unwrap_or(map.len()).min(map.len());
+                Ok((child_pointer(&target_parent, &key), to_index))
+            }
+        }
+        Ok(Value::Array(arr)) => {
+            target_is_array = true;
+            let to_index = index.unwrap_or(arr.len());
+            if to_index > arr.len() {
+                Err(format!("Array index out of bounds: {to_index}"))
+            } else {
+                Ok((child_pointer(&target_parent, &to_index.to_string()), to_index))
+            }
+        }
+        Ok(_) => Err("Target is not an object or array".into()),
+        Err(e) => Err(e),
+    };
+    let (new_pointer, to_index) = match target {
+        Ok(t) => t,
+        Err(e) => {
+            // Put the node back where it was before reporting the error
+            insert_at_pointer(root_mut, &pointer, from_index, value)?;
+            return Err(e);
+        }
+    };
+
+    insert_at_pointer(root_mut, &new_pointer, to_index, value.clone())?;
+    commit_edit(&state, "Move node", vec![
+        EditOp::Remove { pointer, index: from_index, value: value.clone() },
+        EditOp::Add { pointer: new_pointer, index: to_index, value },
+    ]);
+    // Inserting into an array that contains the old parent shifts the old parent along with it
+    let source_parent = if target_is_array { shift_pointer(&source_parent, &target_parent, to_index) } else { source_parent };
+    Ok(MoveResult {
+        source: list_children(&*root_mut, &source_parent, 0, REFRESH_LIMIT),
+        target: list_children(&*root_mut, &target_parent, 0, REFRESH_LIMIT),
+        source_parent,
+        target_parent,
+    })
+}
+
+// `pointer` after an element was inserted at `index` of the array at `array_pointer`
+fn shift_pointer(pointer: &str, array_pointer: &str, index: usize) -> String {
+    let Some(rest) = pointer.strip_prefix(array_pointer).and_then(|r| r.strip_prefix('/')) else {
+        return pointer.to_string();
+    };
+    let (token, tail) = rest.find('/').map_or((rest, ""), |i| rest.split_at(i));
+    match parse_index(token) {
+        Some(i) if i >= index => format!("{array_pointer}/{}{tail}", i + 1),
+        _ => pointer.to_string(),
+    }
 }
\ No newline at end of file
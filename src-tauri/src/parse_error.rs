@@ -0,0 +1,205 @@
+use std::io::{Read, Seek, SeekFrom};
+use serde_json::error::Category;
+use crate::types::{OpenError, ParseError, SourceLine};
+
+// Lines shown before the error line
+const CONTEXT_BEFORE: usize = 2;
+// Context lines are cut to this many characters; the error line is windowed around the error
+const CONTEXT_WIDTH: usize = 120;
+const SCAN_CHUNK: usize = 64 * 1024;
+// Bytes either side of the error inspected when guessing the cause
+const PEEK_BYTES: usize = 64;
+
+// Error for a failed strict parse. `source` must yield the same bytes serde_json read; it is
+// scanned again to find the error, so nothing has to be kept around while parsing.
+pub fn from_serde<R: Read + Seek>(source: R, e: &serde_json::Error) -> OpenError {
+    // I/O errors carry no position
+    if e.line() == 0 {
+        return OpenError::from(e.to_string());
+    }
+    let suffix = format!(" at line {} column {}", e.line(), e.column());
+    let text = e.to_string();
+    let message = text.strip_suffix(&suffix).unwrap_or(&text).to_string();
+    let eof = e.classify() == Category::Eof;
+    at_position(source, e.line(), e.column(), message, eof).unwrap_or_else(|_| OpenError::from(text))
+}
+
+// Error at 1-based `line` and byte `column` (as serde_json counts them) in `source`
+pub fn at_position<R: Read + Seek>(mut source: R, line: usize, column: usize, message: String, eof: bool) -> std::io::Result<OpenError> {
+    let first_line = line.saturating_sub(CONTEXT_BEFORE).max(1);
+    let (starts, offset, column_chars) = scan(&mut source, line, column, first_line)?;
+
+    let mut context = Vec::new();
+    let mut caret = 1;
+    for (i, &start) in starts.iter().enumerate() {
+        let number = first_line + i;
+        if number < line {
+            let text = String::from_utf8_lossy(&read_line(&mut source, start, CONTEXT_WIDTH * 4)?).into_owned();
+            context.push(SourceLine { line: number, text: clip(&text, CONTEXT_WIDTH) });
+            continue;
+        }
+        // Error line: start the window before the error so the caret stays visible
+        let lead = CONTEXT_WIDTH / 2;
+        let window_start = if column_chars > lead { offset.saturating_sub(lead * 4).max(start) } else { start };
+        let bytes = read_line(&mut source, window_start, CONTEXT_WIDTH * 8)?;
+        let skip = bytes.iter().take_while(|b| **b & 0xC0 == 0x80).count(); // start on a char boundary
+        let before_error = &bytes[skip..(offset - window_start).clamp(skip, bytes.len())];
+        let mut text = String::new();
+        if window_start > start {
+            text.push('…');
+        }
+        let lead_text = String::from_utf8_lossy(before_error);
+        let lead_chars: Vec<char> = lead_text.chars().collect();
+        let lead_kept = &lead_chars[lead_chars.len().saturating_sub(lead)..];
+        text.extend(lead_kept);
+        caret = text.chars().count() + 1;
+        let rest = String::from_utf8_lossy(&bytes[skip + before_error.len()..]);
+        text.push_str(&clip(&rest, CONTEXT_WIDTH - lead_kept.len()));
+        context.push(SourceLine { line: number, text });
+    }
+
+    let before = read_at(&mut source, offset.saturating_sub(PEEK_BYTES), offset.min(PEEK_BYTES))?;
+    let after = read_at(&mut source, offset, PEEK_BYTES)?;
+    let guess = guess_cause(&message, eof, &before, &after);
+
+    let parse = ParseError {
+        message,
+        line,
+        column: column_chars,
+        offset,
+        context,
+        caret,
+        cause: guess.map(|(cause, _)| cause.to_string()),
+        hint: guess.map(|(_, hint)| hint.to_string()),
+    };
+    Ok(OpenError { message: render(&parse), parse: Some(Box::new(parse)) })
+}
+
+// One pass over the source: start offsets of the context lines, the byte offset of the error and
+// its column in characters
+fn scan<R: Read>(source: &mut R, line: usize, column: usize, first_line: usize) -> std::io::Result<(Vec<usize>, usize, usize)> {
+    let mut starts = if first_line == 1 { vec![0] } else { vec![] };
+    let mut target = (line == 1).then(|| column.saturating_sub(1));
+    let mut current = 1;
+    let mut pos = 0;
+    let mut chars = 0;
+    let mut buf = vec![0; SCAN_CHUNK];
+    'scan: loop {
+        let n = source.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        for &b in &buf[..n] {
+            match target {
+                Some(t) if pos >= t => break 'scan,
+                Some(_) if b & 0xC0 != 0x80 => chars += 1,
+                Some(_) => {}
+                None if b == b'\n' => {
+                    current += 1;
+                    if current >= first_line {
+                        starts.push(pos + 1);
+                    }
+                    if current == line {
+                        target = Some(pos + 1 + column.saturating_sub(1));
+                    }
+                }
+                None => {}
+            }
+            pos += 1;
+        }
+    }
+    // Errors at end of input can point past the last byte
+    Ok((starts, target.unwrap_or(pos).min(pos), chars + 1))
+}
+
+// Up to `max` bytes of the line starting at `start`, without the line break
+fn read_line<R: Read + Seek>(source: &mut R, start: usize, max: usize) -> std::io::Result<Vec<u8>> {
+    let mut bytes = read_at(source, start, max)?;
+    if let Some(end) = bytes.iter().position(|b| *b == b'\n') {
+        bytes.truncate(end);
+    }
+    if bytes.last() == Some(&b'\r') {
+        bytes.pop();
+    }
+    Ok(bytes)
+}
+
+fn read_at<R: Read + Seek>(source: &mut R, start: usize, len: usize) -> std::io::Result<Vec<u8>> {
+    source.seek(SeekFrom::Start(start as u64))?;
+    let mut bytes = Vec::with_capacity(len);
+    source.take(len as u64).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn clip(text: &str, width: usize) -> String {
+    let mut chars = text.chars();
+    let kept: String = chars.by_ref().take(width).collect();
+    if chars.next().is_some() { format!("{kept}…") } else { kept }
+}
+
+// Best guess at what went wrong, from the parser message and the bytes around the error:
+// (cause code, hint)
+fn guess_cause(message: &str, eof: bool, before: &[u8], after: &[u8]) -> Option<(&'static str, &'static str)> {
+    let next = after.iter().copied().find(|b| !b.is_ascii_whitespace());
+    let prev = before.iter().rev().copied().find(|b| !b.is_ascii_whitespace());
+    if eof {
+        return Some(("truncated", "The document ends before all strings, objects and arrays are closed; the file may be truncated"));
+    }
+    if message.starts_with("trailing comma") || (prev == Some(b',') && matches!(next, Some(b'}' | b']'))) {
+        return Some(("trailing_comma", "JSON does not allow a comma after the last item of an object or array"));
+    }
+    if message.starts_with("control character") {
+        return Some(("control_character", "Strings cannot contain raw line breaks, tabs or other control characters; escape them (\\n, \\t)"));
+    }
+    if message.starts_with("invalid escape") {
+        return Some(("invalid_escape", "Only \\\" \\\\ \\/ \\b \\f \\n \\r \\t and \\uXXXX escapes are allowed in strings"));
+    }
+    if message.starts_with("recursion limit exceeded") {
+        return Some(("too_deep", "Objects and arrays are nested more than 128 levels deep"));
+    }
+    if message.starts_with("trailing characters") {
+        return Some(("trailing_data", "More data follows the first complete value; the file may hold several documents or JSON Lines"));
+    }
+    match next {
+        Some(0xEF) if after.starts_with(&[0xEF, 0xBB, 0xBF]) => Some(("byte_order_mark", "The file starts with a UTF-8 byte order mark, which strict JSON does not allow")),
+        Some(b'/') => Some(("comment", "JSON does not allow comments; open the file in lenient mode to read it")),
+        Some(b'\'') => Some(("single_quotes", "Strings and keys must use double quotes")),
+        Some(b) if message.starts_with("key must be a string") && (b.is_ascii_alphabetic() || b == b'_' || b == b'$') => {
+            Some(("unquoted_key", "Object keys must be double-quoted strings"))
+        }
+        Some(b'N' | b'I' | b'+' | b'u') if message.starts_with("expected value") => {
+            Some(("non_standard_literal", "NaN, Infinity, undefined and leading + are not valid JSON values"))
+        }
+        Some(b'"' | b'{' | b'[' | b'-' | b'0'..=b'9' | b't' | b'f' | b'n') if message.starts_with("expected `,`") => {
+            Some(("missing_comma", "A comma is missing between two items"))
+        }
+        _ => None,
+    }
+}
+
+// Error for a failed lenient (JSON5) parse of `text`. json5 reports columns in characters.
+pub fn from_json5(text: &str, e: &json5::Error) -> OpenError {
+    let json5::Error::Message { msg, location } = e;
+    let Some(location) = location else { return OpenError::from(msg.clone()) };
+    // pest messages end with "= expected …" after their own rendering of the source
+    let message = msg.lines().last().map(|l| l.trim().trim_start_matches("= ").to_string()).unwrap_or_default();
+    let line_text = text.lines().nth(location.line - 1).unwrap_or("");
+    let column = line_text.chars().take(location.column - 1).map(char::len_utf8).sum::<usize>() + 1;
+    let eof = location.line > text.lines().count();
+    at_position(std::io::Cursor::new(text.as_bytes()), location.line, column, message, eof).unwrap_or_else(|_| OpenError::from(msg.clone()))
+}
+
+// `message` text: the parser message with its position, the context lines with a caret under
+// the error, and the likely cause
+fn render(parse: &ParseError) -> String {
+    let width = parse.context.last().map_or(1, |l| l.line.to_string().len());
+    let mut out = format!("{} at line {}, column {}\n\n", parse.message, parse.line, parse.column);
+    for line in &parse.context {
+        out.push_str(&format!("{:>width$} | {}\n", line.line, line.text));
+    }
+    out.push_str(&format!("{:>width$} | {}^", "", " ".repeat(parse.caret - 1)));
+    if let Some(hint) = &parse.hint {
+        out.push_str(&format!("\n\nLikely cause: {hint}"));
+    }
+    out
+}
@@ -0,0 +1,332 @@
+use std::path::Path;
+use std::sync::Arc;
+use serde_json::{json, Value};
+use crate::state::AppState;
+use crate::types::PatchResult;
+use crate::tree::{child_pointer, insert_at_pointer, remove_at_pointer, split_pointer, value_at_mut};
+use crate::history::{apply_op, commit_edit, EditOp};
+use crate::file::write_atomically;
+
+// RFC 6902 operations for recorded edits
+fn edit_op_to_patch(op: &EditOp) -> Value {
+    match op {
+        EditOp::Replace { pointer, new, .. } => json!({ "op": "replace", "path": pointer, "value": new }),
+        EditOp::Add { pointer, value, .. } => json!({ "op": "add", "path": pointer, "value": value }),
+        EditOp::Remove { pointer, .. } => json!({ "op": "remove", "path": pointer }),
+    }
+}
+
+// Generate an RFC 6902 JSON Patch. `source` "edits" (default) covers the edits made since the
+// document was loaded (undone edits excluded); "compare" turns the main document into the
+// compare document. Written to `path` when given; the patch text is returned either way.
+#[tauri::command]
+pub fn export_patch(source: Option<String>, path: Option<String>, state: tauri::State<'_, AppState>) -> Result<String, String> {
+    let patch = match source.as_deref().unwrap_or("edits") {
+        "edits" => {
+            state.main_document()?;
+            let history = state.history.lock();
+            let edits = history.applied_edits()
+                .ok_or("Older edits were dropped from the history; export a diff against the original file instead")?;
+            Value::Array(edits.flat_map(|e| e.ops.iter().map(edit_op_to_patch)).collect())
+        }
+        "compare" => {
+            let left = state.main_document()?.to_value();
+            let right = state.document(Some("compare")).map_err(|_| "No compare document loaded".to_string())?;
+            serde_json::to_value(json_patch::diff(&left, &right)).map_err(|e| e.to_string())?
+        }
+        other => return Err(format!("Unknown patch source: {other}")),
+    };
+    if let Some(path) = path {
+        write_atomically(Path::new(&path), &patch, "json", 2, false)?;
+    }
+    serde_json::to_string_pretty(&patch).map_err(|e| e.to_string())
+}
+
+// Apply a patch to the loaded document as one undoable edit. The patch comes from `patch` text or
+// a file at `path`. `format` is "json-patch" (RFC 6902) or "merge-patch" (RFC 7386); when omitted
+// an array is taken as a JSON Patch and anything else as a merge patch. The patch is applied
+// atomically: if an operation fails (including a `test`), everything is rolled back and the error
+// names the operation and its pointer. `dry_run` validates without keeping the changes.
+#[tauri::command]
+pub fn apply_patch(
+    patch: Option<String>,
+    path: Option<String>,
+    format: Option<String>,
+    dry_run: Option<bool>,
+    state: tauri::State<'_, AppState>
+) -> Result<PatchResult, String> {
+    let text = match (patch, path) {
+        (Some(text), _) => text,
+        (None, Some(path)) => std::fs::read_to_string(&path).map_err(|e| e.to_string())?,
+        (None, None) => return Err("No patch given".into()),
+    };
+    let patch: Value = serde_json::from_str(&text).map_err(|e| format!("Invalid patch JSON: {e}"))?;
+    let format = format.unwrap_or_else(|| if patch.is_array() { "json-patch".into() } else { "merge-patch".into() });
+
+    let mut guard = state.doc_mut();
+    let Some(root_arc) = &mut *guard else { return Err(state.missing_document()); };
+    let root_mut: &mut Value = Arc::make_mut(root_arc);
+
+    let mut ops = Vec::new();
+    let (operations, result) = match format.as_str() {
+        "json-patch" => {
+            let Value::Array(items) = &patch else { return Err("A JSON Patch must be an array of operations".into()); };
+            let result = items.iter().enumerate().try_for_each(|(i, item)| {
+                apply_patch_op(root_mut, item, &mut ops).map_err(|e| {
+                    let op = item.get("op").and_then(Value::as_str).unwrap_or("?");
+                    let path = item.get("path").and_then(Value::as_str).unwrap_or("");
+                    format!("Patch operation {i} ({op}) failed at \"{path}\": {e}")
+                })
+            });
+            (items.len(), result)
+        }
+        "merge-patch" => (1, merge_patch(root_mut, "", &patch, &mut ops)),
+        other => return Err(format!("Unknown patch format: {other}")),
+    };
+
+    let dry_run = dry_run.unwrap_or(false);
+    if result.is_err() || dry_run {
+        // Roll back in reverse so the document is exactly as before
+        for op in ops.iter().rev() {
+            apply_op(root_mut, op, true)?;
+        }
+        result?;
+    }
+    let changes = ops.len();
+    if !dry_run && changes > 0 {
+        commit_edit(&state, "Apply patch", ops);
+    }
+    Ok(PatchResult { format, operations, changes, applied: !dry_run })
+}
+
+fn apply_patch_op(root: &mut Value, item: &Value, ops: &mut Vec<EditOp>) -> Result<(), String> {
+    let op = item.get("op").and_then(Value::as_str).ok_or("missing \"op\"")?;
+    let path = pointer_member(item, "path")?;
+    let value = || item.get("value").cloned().ok_or("missing \"value\"");
+    match op {
+        "add" => add(root, path, value()?, ops),
+        "remove" => remove(root, path, ops).map(|_| ()),
+        "replace" => {
+            let target = root.pointer_mut(path).ok_or("path does not exist")?;
+            let new = value()?;
+            let old = std::mem::replace(target, new.clone());
+            ops.push(EditOp::Replace { pointer: path.to_string(), old, new });
+            Ok(())
+        }
+        "move" => {
+            let from = pointer_member(item, "from")?;
+            if from == path {
+                return Ok(());
+            }
+            if path.starts_with(&format!("{from}/")) {
+                return Err("cannot move a value into itself".into());
+            }
+            let moved = remove(root, from, ops)?;
+            add(root, path, moved, ops)
+        }
+        "copy" => {
+            let from = pointer_member(item, "from")?;
+            let copied = root.pointer(from).cloned().ok_or("\"from\" does not exist")?;
+            add(root, path, copied, ops)
+        }
+        "test" => {
+            let actual = root.pointer(path).ok_or("path does not exist")?;
+            if !json_equal(actual, &value()?) {
+                return Err(format!("test failed, value is {}", preview(actual)));
+            }
+            Ok(())
+        }
+        other => Err(format!("unknown op \"{other}\"")),
+    }
+}
+
+// Equality as RFC 6902 "test" defines it: structural, except that numbers compare by value, so
+// 1, 1.0 and 1e0 are equal. Numbers keep their literal text, so they are compared exactly.
+// Diffs and merge patches use it too, so a number only re-spelled doesn't count as a change.
+pub fn json_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => decimal(x.as_str()) == decimal(y.as_str()),
+        (Value::Array(x), Value::Array(y)) => x.len() == y.len() && x.iter().zip(y).all(|(a, b)| json_equal(a, b)),
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len() && x.iter().all(|(key, a)| y.get(key).is_some_and(|b| json_equal(a, b)))
+        }
+        _ => a == b,
+    }
+}
+
+// A number literal as (negative, significant digits, exponent), so that equal values give equal
+// triples: 1.50e2 -> (false, "15", 1). Zero is (false, "", 0) whatever its sign.
+fn decimal(literal: &str) -> (bool, String, i64) {
+    let (negative, unsigned) = match literal.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, literal),
+    };
+    let (mantissa, exponent) = unsigned.split_once(['e', 'E']).unwrap_or((unsigned, "0"));
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let mut exponent = exponent.parse::<i64>().unwrap_or(0) - frac.len() as i64;
+    let digits = format!("{int}{frac}");
+    let digits = digits.trim_start_matches('0');
+    let significant = digits.trim_end_matches('0');
+    exponent += (digits.len() - significant.len()) as i64;
+    if significant.is_empty() {
+        return (false, String::new(), 0);
+    }
+    (negative, significant.to_string(), exponent)
+}
+
+fn pointer_member<'a>(item: &'a Value, name: &str) -> Result<&'a str, String> {
+    let pointer = item.get(name).and_then(Value::as_str).ok_or_else(|| format!("missing \"{name}\""))?;
+    if !pointer.is_empty() && !pointer.starts_with('/') {
+        return Err(format!("invalid JSON Pointer \"{pointer}\""));
+    }
+    Ok(pointer)
+}
+
+fn preview(v: &Value) -> String {
+    let text = v.to_string();
+    match text.char_indices().nth(80) {
+        Some((cut, _)) => format!("{}…", &text[..cut]),
+        None => text,
+    }
+}
+
+// RFC 6902 "add": replaces the whole document at "", sets (or overwrites) an object member,
+// or inserts into an array at an index or at the end ("-")
+fn add(root: &mut Value, path: &str, value: Value, ops: &mut Vec<EditOp>) -> Result<(), String> {
+    let Some((parent_ptr, token)) = split_pointer(path) else {
+        let old = std::mem::replace(root, value.clone());
+        ops.push(EditOp::Replace { pointer: String::new(), old, new: value });
+        return Ok(());
+    };
+    let parent = root.pointer_mut(parent_ptr).ok_or("parent does not exist")?;
+    let (pointer, index) = match parent {
+        Value::Object(map) => {
+            if let Some(existing) = map.get_mut(&token) {
+                let old = std::mem::replace(existing, value.clone());
+                ops.push(EditOp::Replace { pointer: path.to_string(), old, new: value });
+                return Ok(());
+            }
+            (path.to_string(), map.len())
+        }
+        Value::Array(arr) => {
+            let index = if token == "-" { arr.len() } else { array_index(&token)? };
+            if index > arr.len() {
+                return Err(format!("index {index} is out of bounds"));
+            }
+            (child_pointer(parent_ptr, &index.to_string()), index)
+        }
+        _ => return Err("parent is not an object or array".into()),
+    };
+    insert_at_pointer(root, &pointer, index, value.clone())?;
+    ops.push(EditOp::Add { pointer, index, value });
+    Ok(())
+}
+
+fn remove(root: &mut Value, path: &str, ops: &mut Vec<EditOp>) -> Result<Value, String> {
+    if path.is_empty() {
+        return Err("cannot remove the document root".into());
+    }
+    if let Some((parent_ptr, token)) = split_pointer(path) {
+        if root.pointer(parent_ptr).is_some_and(Value::is_array) {
+            array_index(&token)?;
+        }
+    }
+    let (index, value) = remove_at_pointer(root, path).map_err(|_| "path does not exist")?;
+    ops.push(EditOp::Remove { pointer: path.to_string(), index, value: value.clone() });
+    Ok(value)
+}
+
+// Array indices are plain decimal without leading zeros (RFC 6901)
+fn array_index(token: &str) -> Result<usize, String> {
+    let valid = !token.is_empty() && token.bytes().all(|b| b.is_ascii_digit()) && (token == "0" || !token.starts_with('0'));
+    if !valid {
+        return Err(format!("invalid array index \"{token}\""));
+    }
+    token.parse().map_err(|_| format!("invalid array index \"{token}\""))
+}
+
+// RFC 7386: objects merge member by member, null removes a member, anything else replaces
+fn merge_patch(root: &mut Value, pointer: &str, patch: &Value, ops: &mut Vec<EditOp>) -> Result<(), String> {
+    let target = value_at_mut(root, pointer)?;
+    let Value::Object(members) = patch else {
+        return replace_with(target, pointer, patch.clone(), ops);
+    };
+    if !target.is_object() {
+        return replace_with(target, pointer, without_nulls(patch), ops);
+    }
+    for (key, value) in members {
+        let child = child_pointer(pointer, key);
+        let Value::Object(map) = value_at_mut(root, pointer)? else { unreachable!() };
+        match (map.contains_key(key), value.is_null()) {
+            (true, true) => {
+                let (index, old) = remove_at_pointer(root, &child)?;
+                ops.push(EditOp::Remove { pointer: child, index, value: old });
+            }
+            (true, false) => merge_patch(root, &child, value, ops)?,
+            (false, true) => {}
+            (false, false) => {
+                let index = map.len();
+                let value = without_nulls(value);
+                insert_at_pointer(root, &child, index, value.clone())?;
+                ops.push(EditOp::Add { pointer: child, index, value });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn replace_with(target: &mut Value, pointer: &str, new: Value, ops: &mut Vec<EditOp>) -> Result<(), String> {
+    if !json_equal(target, &new) {
+        let old = std::mem::replace(target, new.clone());
+        ops.push(EditOp::Replace { pointer: pointer.to_string(), old, new });
+    }
+    Ok(())
+}
+
+// A merge patch applied to a non-object: its null members mean "absent"
+fn without_nulls(patch: &Value) -> Value {
+    match patch {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k.clone(), without_nulls(v)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn number(text: &str) -> Value {
+        serde_json::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn numbers_compare_by_value() {
+        for (a, b) in [("1", "1.0"), ("1e2", "100"), ("100", "1.00E+2"), ("-0", "0.0"), ("0.5", "5e-1"), ("12345678901234567890", "1.234567890123456789e19")] {
+            assert!(json_equal(&number(a), &number(b)), "{a} == {b}");
+        }
+        for (a, b) in [("1", "-1"), ("1", "10"), ("12345678901234567890", "12345678901234567891"), ("0.1", "0.01")] {
+            assert!(!json_equal(&number(a), &number(b)), "{a} != {b}");
+        }
+    }
+
+    #[test]
+    fn containers_compare_numbers_by_value() {
+        assert!(json_equal(&number(r#"{"a": [1, 2.0], "b": {"c": 3e0}}"#), &json!({"b": {"c": 3}, "a": [1, 2]})));
+        assert!(!json_equal(&json!([1, 2]), &json!([2, 1])));
+        assert!(!json_equal(&json!({"a": 1}), &json!({"a": 1, "b": 2})));
+    }
+
+    #[test]
+    fn test_op_accepts_equal_numbers() {
+        let mut root = number(r#"{"n": 1e2}"#);
+        let mut ops = Vec::new();
+        assert!(apply_patch_op(&mut root, &json!({"op": "test", "path": "/n", "value": 100}), &mut ops).is_ok());
+        assert!(apply_patch_op(&mut root, &json!({"op": "test", "path": "/n", "value": 101}), &mut ops).is_err());
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+use jaq_core::load::{Arena, File, Loader};
+use jaq_core::{Compiler, Ctx, Filter, Native, RcIter};
+use jaq_json::Val;
+use serde_json::Value;
+use serde_json_path::JsonPath;
+use tauri::async_runtime::spawn_blocking;
+use crate::state::AppState;
+use crate::types::{SearchResult, SearchResponse, TransformResult};
+use crate::tree::{create_node_for_path, list_children};
+use crate::history::{commit_edit, EditOp};
+
+// Guard against non-terminating filters such as `repeat(1)`
+const MAX_JQ_OUTPUTS: usize = 1_000_000;
+
+// Evaluate a JSONPath expression (RFC 9535: wildcards, recursive descent `..`, slices,
+// filters like `$.store.book[?(@.price < 10)].title`) against the loaded document.
+// Matches come back as SearchResults in document order, paginated like `search`.
+#[tauri::command]
+pub async fn query_jsonpath(
+    expression: String,
+    offset: usize,
+    limit: usize,
+    state: tauri::State<'_, AppState>
+) -> Result<SearchResponse, String> {
+    let root_arc = state.document(None)?;
+
+    let path = JsonPath::parse(expression.trim()).map_err(|e| format!("Invalid JSONPath: {e}"))?;
+
+    let (results, total_count) = spawn_blocking(move || {
+        let located = path.query_located(&root_arc);
+        let total = located.len();
+        let page: Vec<SearchResult> = located
+            .iter()
+            .skip(offset)
+            .take(limit)
+            .map(|m| {
+                let pointer = m.location().to_json_pointer();
+                SearchResult {
+                    node: create_node_for_path(m.node(), &pointer),
+                    match_type: "jsonpath".into(),
+                    match_text: m.location().to_string(),
+                    context: None,
+                    ranges: vec![],
+                    snippet: None,
+                }
+            })
+            .collect();
+        (page, total)
+    })
+    .await
+    .map_err(|e| format!("Join error: {e}"))?;
+
+    let has_more = offset + limit < total_count;
+    Ok(SearchResponse { results, total_count, has_more })
+}
+
+
+fn compile_jq(code: &str) -> Result<Filter<Native<Val>>, String> {
+    let loader = Loader::new(jaq_std::defs().chain(jaq_json::defs()));
+    let arena = Arena::default();
+    let modules = loader.load(&arena, File { code, path: () }).map_err(|errs| {
+        let messages: Vec<String> = errs.into_iter().flat_map(|(_, e)| match e {
+            jaq_core::load::Error::Io(items) => items.into_iter().map(|(_, msg)| msg).collect::<Vec<_>>(),
+            jaq_core::load::Error::Lex(items) => items.into_iter()
+                .map(|(expect, found)| format!("expected {} at `{}`", expect.as_str(), found.chars().take(20).collect::<String>()))
+                .collect(),
+            jaq_core::load::Error::Parse(items) => items.into_iter()
+                .map(|(expect, found)| format!("expected {} at `{}`", expect.as_str(), found.chars().take(20).collect::<String>()))
+                .collect(),
+        }).collect();
+        format!("Invalid jq filter: {}", messages.join("; "))
+    })?;
+    Compiler::default()
+        .with_funs(jaq_std::funs().chain(jaq_json::funs()))
+        .compile(modules)
+        .map_err(|errs| {
+            let messages: Vec<String> = errs.into_iter()
+                .flat_map(|(_, undefined)| undefined)
+                .map(|(name, kind)| format!("undefined {} `{}`", kind.as_str(), name))
+                .collect();
+            format!("Invalid jq filter: {}", messages.join("; "))
+        })
+}
+
+// Run a jq filter (jaq dialect, e.g. `.items[] | {id, name}` or `map(select(.active))`) on the
+// loaded document. A single output becomes the result document as-is; several outputs are
+// collected into an array. The result is browsable via load_children with source "result", or,
+// with `replace`, becomes the loaded document as one undoable edit.
+#[tauri::command]
+pub async fn run_jq(filter: String, replace: Option<bool>, state: tauri::State<'_, AppState>) -> Result<TransformResult, String> {
+    let snapshot = state.document(None)?;
+    let root_arc = snapshot.clone();
+
+    let (result, output_count) = spawn_blocking(move || -> Result<(Value, usize), String> {
+        let compiled = compile_jq(&filter)?;
+        let inputs = RcIter::new(core::iter::empty());
+        let mut outputs = Vec::new();
+        for out in compiled.run((Ctx::new([], &inputs), Val::from((*root_arc).clone()))) {
+            let val = out.map_err(|e| format!("jq error: {e}"))?;
+            outputs.push(Value::from(val));
+            if outputs.len() > MAX_JQ_OUTPUTS {
+                return Err(format!("jq filter produced more than {MAX_JQ_OUTPUTS} outputs"));
+            }
+        }
+        let count = outputs.len();
+        let result = if count == 1 { outputs.pop().unwrap_or(Value::Null) } else { Value::Array(outputs) };
+        Ok((result, count))
+    })
+    .await
+    .map_err(|e| format!("Join error: {e}"))??;
+
+    if replace.unwrap_or(false) {
+        let mut guard = state.doc_mut();
+        let Some(root_arc) = &mut *guard else { return Err(state.missing_document()); };
+        // An edit that landed while the filter ran would be overwritten
+        if !Arc::ptr_eq(root_arc, &snapshot) {
+            return Err("Document changed while running the filter; please retry".into());
+        }
+        drop(snapshot);
+        let old = std::mem::replace(root_arc, Arc::new(result.clone()));
+        // Only copied when another reader still holds the old document
+        let old = Arc::try_unwrap(old).unwrap_or_else(|shared| (*shared).clone());
+        let nodes = list_children(root_arc.as_ref(), "", 0, 100);
+        commit_edit(&state, "jq transform", vec![EditOp::Replace { pointer: String::new(), old, new: result }]);
+        return Ok(TransformResult { output_count, replaced: true, nodes });
+    }
+
+    let nodes = list_children(&result, "", 0, 100);
+    *state.result_doc.write() = Some(Arc::new(result));
+    Ok(TransformResult { output_count, replaced: false, nodes })
+}
@@ -0,0 +1,675 @@
+use serde_json::{Map, Number, Value};
+use crate::tree::escape_pointer_token;
+use crate::types::Repair;
+
+// Cap on stored repairs; a badly corrupted file can need one per token
+const MAX_REPAIRS: usize = 1000;
+// Nesting limit, the same as serde_json's
+const MAX_DEPTH: usize = 128;
+
+// Best-effort parse of malformed or truncated JSON. Everything that parses is kept; unterminated
+// strings and containers are closed, missing values become null, missing commas and colons are
+// assumed and anything else that does not fit is skipped. Returns the recovered value, the
+// repairs made (capped at MAX_REPAIRS) and the total repair count. Valid JSON comes back
+// unchanged with no repairs.
+pub fn recover(src: &[u8]) -> (Value, Vec<Repair>, usize) {
+    let mut parser = Recovery { src, pos: 0, stack: Vec::new(), repairs: Vec::new(), repair_count: 0 };
+    let root = parser.run();
+    (root, parser.repairs, parser.repair_count)
+}
+
+enum Frame {
+    Array { items: Vec<Value>, comma: Option<usize> },
+    // `key` is the member whose value comes next
+    Object { map: Map<String, Value>, key: Option<String>, comma: Option<usize> },
+}
+
+// What the parser expects next
+#[derive(Clone, Copy)]
+enum Expect {
+    Value,
+    Key,
+    Colon,
+    // A comma or closing bracket after a value; at the top level, the end of input
+    Separator,
+}
+
+struct Recovery<'a> {
+    src: &'a [u8],
+    pos: usize,
+    stack: Vec<Frame>,
+    repairs: Vec<Repair>,
+    repair_count: usize,
+}
+
+impl Recovery<'_> {
+    fn run(&mut self) -> Value {
+        if self.src.starts_with(&[0xEF, 0xBB, 0xBF]) {
+            self.pos = 3;
+        }
+        let mut expect = Expect::Value;
+        let mut root = None;
+        loop {
+            self.skip_ws();
+            let Some(&b) = self.src.get(self.pos) else { break };
+            expect = match expect {
+                Expect::Value => match b {
+                    b'{' | b'[' => self.open(b),
+                    b'"' | b'-' | b'0'..=b'9' | b't' | b'f' | b'n' => match self.scalar(b) {
+                        Some(value) => self.attach(value, &mut root),
+                        None => self.after_skip(),
+                    },
+                    b']' | b'}' => self.close(b, &mut root, Expect::Value),
+                    b',' => self.value_before_comma(&mut root),
+                    _ => {
+                        self.skip_token();
+                        self.after_skip()
+                    }
+                },
+                Expect::Key => match b {
+                    b'"' => {
+                        let key = self.string();
+                        if let Some(Frame::Object { key: slot, .. }) = self.stack.last_mut() {
+                            *slot = Some(key);
+                        }
+                        Expect::Colon
+                    }
+                    b'}' | b']' => self.close(b, &mut root, Expect::Key),
+                    b',' => {
+                        self.repair("remove_comma", self.pos, 1, self.path(), "Removed extra comma".into());
+                        self.pos += 1;
+                        Expect::Key
+                    }
+                    _ => {
+                        let start = self.pos;
+                        self.skip_balanced();
+                        self.skipped(start);
+                        if let Some(Frame::Object { comma, .. }) = self.stack.last_mut() {
+                            *comma = None;
+                        }
+                        Expect::Separator
+                    }
+                },
+                Expect::Colon => match b {
+                    b':' => {
+                        self.pos += 1;
+                        Expect::Value
+                    }
+                    b'{' | b'[' | b'"' | b'-' | b'0'..=b'9' | b't' | b'f' | b'n' => {
+                        self.repair("insert_colon", self.pos, 0, self.child_path(), "Inserted missing colon".into());
+                        Expect::Value
+                    }
+                    b',' | b'}' | b']' => {
+                        self.fill_missing_value(self.pos, &mut root);
+                        Expect::Separator
+                    }
+                    _ => {
+                        self.skip_token();
+                        Expect::Colon
+                    }
+                },
+                Expect::Separator if self.stack.is_empty() => {
+                    let length = self.src.len() - self.pos;
+                    self.repair("skip_trailing", self.pos, length, String::new(), format!("Skipped {length} bytes after the end of the document"));
+                    self.pos = self.src.len();
+                    break;
+                }
+                Expect::Separator => match b {
+                    b',' => {
+                        let at = self.pos;
+                        self.pos += 1;
+                        match self.stack.last_mut() {
+                            Some(Frame::Array { comma, .. }) => {
+                                *comma = Some(at);
+                                Expect::Value
+                            }
+                            Some(Frame::Object { comma, .. }) => {
+                                *comma = Some(at);
+                                Expect::Key
+                            }
+                            None => unreachable!(),
+                        }
+                    }
+                    b'}' | b']' => self.close(b, &mut root, Expect::Separator),
+                    b'{' | b'[' | b'"' | b'-' | b'0'..=b'9' | b't' | b'f' | b'n' => {
+                        self.repair("insert_comma", self.pos, 0, self.path(), "Inserted missing comma".into());
+                        match self.stack.last() {
+                            Some(Frame::Object { .. }) => Expect::Key,
+                            _ => Expect::Value,
+                        }
+                    }
+                    _ => {
+                        self.skip_token();
+                        Expect::Separator
+                    }
+                },
+            };
+        }
+
+        // End of input: close whatever is still open
+        let end = self.src.len();
+        if self.stack.is_empty() && root.is_none() {
+            self.repair("insert_value", end, 0, String::new(), "The document is empty; loaded null".into());
+            return Value::Null;
+        }
+        while !self.stack.is_empty() {
+            self.fill_missing_value(end, &mut root);
+            self.close_top(end, &mut root);
+        }
+        root.unwrap_or(Value::Null)
+    }
+
+    // Open an object or array. Past MAX_DEPTH the whole container is skipped instead, as the
+    // strict parser's recursion limit would reject it and deeper trees are slow to drop.
+    fn open(&mut self, b: u8) -> Expect {
+        if self.stack.len() >= MAX_DEPTH {
+            let start = self.pos;
+            self.skip_balanced();
+            let length = self.pos - start;
+            self.repair("skip_invalid", start, length, self.child_path(), format!("Skipped {length} bytes nested more than {MAX_DEPTH} levels deep"));
+            return self.after_skip();
+        }
+        self.pos += 1;
+        if b == b'{' {
+            self.stack.push(Frame::Object { map: Map::new(), key: None, comma: None });
+            Expect::Key
+        } else {
+            self.stack.push(Frame::Array { items: Vec::new(), comma: None });
+            Expect::Value
+        }
+    }
+
+    // String, number or literal at the current position; None if it was skipped as invalid
+    fn scalar(&mut self, b: u8) -> Option<Value> {
+        match b {
+            b'"' => Some(Value::String(self.string())),
+            b'-' | b'0'..=b'9' => self.number(),
+            _ => self.literal(),
+        }
+    }
+
+    // What follows a skipped value: in an array the bad input takes the place of an element, in
+    // an object the member's value is still missing
+    fn after_skip(&mut self) -> Expect {
+        match self.stack.last_mut() {
+            Some(Frame::Array { comma, .. }) => {
+                *comma = None;
+                Expect::Separator
+            }
+            _ => Expect::Value,
+        }
+    }
+
+    // A comma where a value should be: an array element or object member value is missing
+    fn value_before_comma(&mut self, root: &mut Option<Value>) -> Expect {
+        match self.stack.last() {
+            Some(Frame::Object { key: Some(_), .. }) => {
+                self.fill_missing_value(self.pos, root);
+                Expect::Separator
+            }
+            Some(Frame::Array { .. }) => {
+                self.repair("remove_comma", self.pos, 1, self.path(), "Removed extra comma".into());
+                self.pos += 1;
+                Expect::Value
+            }
+            _ => {
+                self.skip_token();
+                Expect::Value
+            }
+        }
+    }
+
+    // Add a finished value to the open container, or make it the root
+    fn attach(&mut self, value: Value, root: &mut Option<Value>) -> Expect {
+        match self.stack.last_mut() {
+            Some(Frame::Array { items, comma }) => {
+                items.push(value);
+                *comma = None;
+            }
+            Some(Frame::Object { map, key, comma }) => {
+                if let Some(key) = key.take() {
+                    map.insert(key, value);
+                }
+                *comma = None;
+            }
+            None => *root = Some(value),
+        }
+        Expect::Separator
+    }
+
+    // Closing bracket `b`. Containers opened inside the one it matches are closed first; a
+    // bracket that matches nothing open is skipped.
+    fn close(&mut self, b: u8, root: &mut Option<Value>, expect: Expect) -> Expect {
+        let object = b == b'}';
+        let Some(depth) = self.stack.iter().rposition(|f| matches!(f, Frame::Object { .. }) == object) else {
+            self.skip_token();
+            return expect;
+        };
+        while self.stack.len() > depth + 1 {
+            self.fill_missing_value(self.pos, root);
+            self.close_top(self.pos, root);
+        }
+        self.fill_missing_value(self.pos, root);
+        if let Some(Frame::Array { comma: Some(at), .. } | Frame::Object { comma: Some(at), .. }) = self.stack.last() {
+            let at = *at;
+            self.repair("remove_comma", at, 1, self.path(), "Removed trailing comma".into());
+        }
+        let frame = self.stack.pop();
+        self.pos += 1;
+        match frame {
+            Some(Frame::Array { items, .. }) => self.attach(Value::Array(items), root),
+            Some(Frame::Object { map, .. }) => self.attach(Value::Object(map), root),
+            None => expect,
+        }
+    }
+
+    // Close the innermost container with a synthesized bracket at `at`
+    fn close_top(&mut self, at: usize, root: &mut Option<Value>) {
+        let path = self.path();
+        let value = match self.stack.pop() {
+            Some(Frame::Array { items, .. }) => {
+                self.repair("close_container", at, 0, path, "Closed unterminated array".into());
+                Value::Array(items)
+            }
+            Some(Frame::Object { map, .. }) => {
+                self.repair("close_container", at, 0, path, "Closed unterminated object".into());
+                Value::Object(map)
+            }
+            None => return,
+        };
+        self.attach(value, root);
+    }
+
+    // If the innermost object has a key waiting for its value, give it null
+    fn fill_missing_value(&mut self, at: usize, root: &mut Option<Value>) {
+        let Some(Frame::Object { key: Some(key), .. }) = self.stack.last() else { return };
+        let message = format!("Inserted null for the missing value of \"{key}\"");
+        self.repair("insert_value", at, 0, self.child_path(), message);
+        self.attach(Value::Null, root);
+    }
+
+    // String starting at the opening quote. An unterminated string ends at the next raw line
+    // break (strings cannot contain one) or at the end of input.
+    fn string(&mut self) -> String {
+        let start = self.pos;
+        self.pos += 1;
+        let mut out = Vec::new();
+        loop {
+            let Some(&b) = self.src.get(self.pos) else {
+                self.repair("close_string", self.pos, 0, self.child_path(), "Closed unterminated string".into());
+                break;
+            };
+            match b {
+                b'"' => {
+                    self.pos += 1;
+                    break;
+                }
+                b'\n' => {
+                    self.repair("close_string", self.pos, 0, self.child_path(), "Closed string at line break".into());
+                    break;
+                }
+                b'\\' => self.escape(&mut out),
+                _ => {
+                    out.push(b);
+                    self.pos += 1;
+                }
+            }
+        }
+        match String::from_utf8(out) {
+            Ok(s) => s,
+            Err(e) => {
+                let length = self.pos - start;
+                self.repair("invalid_utf8", start, length, self.child_path(), "Replaced invalid UTF-8 in string".into());
+                String::from_utf8_lossy(e.as_bytes()).into_owned()
+            }
+        }
+    }
+
+    // Escape sequence at a backslash, appended to `out` as UTF-8
+    fn escape(&mut self, out: &mut Vec<u8>) {
+        let start = self.pos;
+        self.pos += 1;
+        let Some(&b) = self.src.get(self.pos) else { return };
+        self.pos += 1;
+        let simple = match b {
+            b'"' => Some(b'"'),
+            b'\\' => Some(b'\\'),
+            b'/' => Some(b'/'),
+            b'b' => Some(0x08),
+            b'f' => Some(0x0C),
+            b'n' => Some(b'\n'),
+            b'r' => Some(b'\r'),
+            b't' => Some(b'\t'),
+            _ => None,
+        };
+        if let Some(c) = simple {
+            out.push(c);
+            return;
+        }
+        if b == b'u' {
+            if let Some(c) = self.unicode_escape() {
+                out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                return;
+            }
+            let length = self.pos - start;
+            self.repair("invalid_escape", start, length, self.child_path(), "Replaced invalid \\u escape with U+FFFD".into());
+            out.extend_from_slice("\u{FFFD}".as_bytes());
+            return;
+        }
+        // Unknown escape: keep the character, drop the backslash
+        self.pos -= 1;
+        self.repair("invalid_escape", start, 1, self.child_path(), "Dropped backslash of invalid escape".into());
+    }
+
+    // Code point of a \u escape (after the "u"), combining surrogate pairs
+    fn unicode_escape(&mut self) -> Option<char> {
+        let high = self.hex4()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high);
+        }
+        if self.src.get(self.pos..self.pos + 2) != Some(b"\\u") {
+            return None;
+        }
+        self.pos += 2;
+        let low = self.hex4()?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return None;
+        }
+        char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let digits = self.src.get(self.pos..self.pos + 4)?;
+        if !digits.iter().all(u8::is_ascii_hexdigit) {
+            return None;
+        }
+        let code = u32::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()?;
+        self.pos += 4;
+        Some(code)
+    }
+
+    // Number at the current position. Only the longest valid prefix of the token is used.
+    fn number(&mut self) -> Option<Value> {
+        let start = self.pos;
+        let token = self.src[start..].iter().take_while(|b| matches!(b, b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E')).count();
+        let valid = number_prefix(&self.src[start..start + token]);
+        if valid == 0 {
+            self.skip_token();
+            return None;
+        }
+        let text = std::str::from_utf8(&self.src[start..start + valid]).unwrap_or("0");
+        self.pos = start + token;
+        if valid < token {
+            self.repair("truncate_number", start + valid, token - valid, self.child_path(), format!("Kept {text} of malformed number"));
+        }
+        match serde_json::from_str::<Number>(text) {
+            Ok(n) => Some(Value::Number(n)),
+            Err(_) => {
+                self.repair("insert_value", start, valid, self.child_path(), format!("Replaced out-of-range number {text} with null"));
+                Some(Value::Null)
+            }
+        }
+    }
+
+    // true, false or null; a literal cut off by the end of input is completed
+    fn literal(&mut self) -> Option<Value> {
+        let rest = self.src[self.pos..].trim_ascii_end();
+        for (word, value) in [("true", Value::Bool(true)), ("false", Value::Bool(false)), ("null", Value::Null)] {
+            if rest.starts_with(word.as_bytes()) {
+                self.pos += word.len();
+                return Some(value);
+            }
+            if word.as_bytes().starts_with(rest) {
+                self.repair("complete_literal", self.pos + rest.len(), 0, self.child_path(), format!("Completed truncated literal {word}"));
+                self.pos = self.src.len();
+                return Some(value);
+            }
+        }
+        self.skip_token();
+        None
+    }
+
+    // Skip one bad token: a run of anything but whitespace and JSON punctuation, or a single
+    // punctuation byte that is out of place. Adjacent skips are reported as one repair.
+    fn skip_token(&mut self) {
+        let start = self.pos;
+        let run = self.src[start..].iter().take_while(|b| !b.is_ascii_whitespace() && !b"{}[],:\"".contains(b)).count();
+        self.pos += run.max(1);
+        self.skipped(start);
+    }
+
+    // Skip up to the next comma or closing bracket at this nesting level
+    fn skip_balanced(&mut self) {
+        let mut depth = 0usize;
+        let mut in_string = false;
+        while let Some(&b) = self.src.get(self.pos) {
+            match b {
+                _ if in_string => match b {
+                    b'\\' => self.pos += 1,
+                    b'"' | b'\n' => in_string = false,
+                    _ => {}
+                },
+                b'"' => in_string = true,
+                b'{' | b'[' => depth += 1,
+                b'}' | b']' if depth == 0 => break,
+                b'}' | b']' => depth -= 1,
+                b',' if depth == 0 => break,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        self.pos = self.pos.min(self.src.len());
+    }
+
+    fn skipped(&mut self, start: usize) {
+        let length = self.pos - start;
+        // Merge with a previous skip separated from this one only by whitespace
+        if let Some(last) = self.repairs.last_mut() {
+            let end = last.offset + last.length;
+            if last.action == "skip_invalid" && end <= start && self.src[end..start].iter().all(u8::is_ascii_whitespace) {
+                last.length = self.pos - last.offset;
+                last.message = format!("Skipped {} bytes of invalid input", last.length);
+                return;
+            }
+        }
+        self.repair("skip_invalid", start, length, self.child_path(), format!("Skipped {length} bytes of invalid input"));
+    }
+
+    fn skip_ws(&mut self) {
+        while self.src.get(self.pos).is_some_and(u8::is_ascii_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    // Pointer of the innermost open container
+    fn path(&self) -> String {
+        let parents = &self.stack[..self.stack.len().saturating_sub(1)];
+        parents.iter().map(|frame| format!("/{}", slot_token(frame))).collect()
+    }
+
+    // Pointer of the value being parsed in the innermost open container
+    fn child_path(&self) -> String {
+        match self.stack.last() {
+            Some(frame @ (Frame::Array { .. } | Frame::Object { key: Some(_), .. })) => format!("{}/{}", self.path(), slot_token(frame)),
+            _ => self.path(),
+        }
+    }
+
+    fn repair(&mut self, action: &str, offset: usize, length: usize, pointer: String, message: String) {
+        self.repair_count += 1;
+        if self.repairs.len() < MAX_REPAIRS {
+            self.repairs.push(Repair { action: action.into(), offset, length, pointer, message });
+        }
+    }
+}
+
+// Pointer token of the child a container is parsing: the next array index or the pending key
+fn slot_token(frame: &Frame) -> String {
+    match frame {
+        Frame::Array { items, .. } => items.len().to_string(),
+        Frame::Object { key, .. } => key.as_deref().map(escape_pointer_token).unwrap_or_default(),
+    }
+}
+
+// Length of the longest prefix of `token` that is a complete JSON number
+fn number_prefix(token: &[u8]) -> usize {
+    let digits = |from: usize| token[from..].iter().take_while(|b| b.is_ascii_digit()).count();
+    let mut end = usize::from(token.first() == Some(&b'-'));
+    let int = digits(end);
+    if int == 0 {
+        return 0;
+    }
+    // A leading zero ends the integer part
+    end += if token[end] == b'0' { 1 } else { int };
+    let mut valid = end;
+    if token.get(end) == Some(&b'.') {
+        let frac = digits(end + 1);
+        if frac == 0 {
+            return valid;
+        }
+        end += 1 + frac;
+        valid = end;
+    }
+    if matches!(token.get(end), Some(b'e' | b'E')) {
+        end += 1;
+        if matches!(token.get(end), Some(b'+' | b'-')) {
+            end += 1;
+        }
+        let exp = digits(end);
+        if exp > 0 {
+            valid = end + exp;
+        }
+    }
+    valid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // Recovered value and (action, offset, length, pointer) of each repair
+    fn run(src: &[u8]) -> (Value, Vec<(String, usize, usize, String)>) {
+        let (value, repairs, count) = recover(src);
+        assert_eq!(count, repairs.len());
+        let repairs = repairs.into_iter().map(|r| (r.action, r.offset, r.length, r.pointer)).collect();
+        (value, repairs)
+    }
+
+    fn check(src: &str, expected: Value, repairs: &[(&str, usize, usize, &str)]) {
+        let (value, found) = run(src.as_bytes());
+        assert_eq!(value, expected, "value for {src:?}");
+        let expected: Vec<_> = repairs.iter().map(|&(a, o, l, p)| (a.to_string(), o, l, p.to_string())).collect();
+        assert_eq!(found, expected, "repairs for {src:?}");
+    }
+
+    #[test]
+    fn valid_input_is_unchanged() {
+        let src = r#"{"a": [1, 2.50, "xé", true], "b": {"c": null}, "d": -0.5e3}"#;
+        check(src, serde_json::from_str(src).unwrap(), &[]);
+    }
+
+    #[test]
+    fn close_string() {
+        check(r#"{"a": "abc"#, json!({"a": "abc"}), &[("close_string", 10, 0, "/a"), ("close_container", 10, 0, "")]);
+        check("[\"ab\n, 1]", json!(["ab", 1]), &[("close_string", 4, 0, "/0")]);
+    }
+
+    #[test]
+    fn close_container() {
+        let src = r#"{"a": [1, {"b": 2"#;
+        let end = src.len();
+        check(src, json!({"a": [1, {"b": 2}]}), &[
+            ("close_container", end, 0, "/a/1"),
+            ("close_container", end, 0, "/a"),
+            ("close_container", end, 0, ""),
+        ]);
+        // A closing bracket closes the containers opened inside the one it matches
+        check(r#"{"a": [1, 2}"#, json!({"a": [1, 2]}), &[("close_container", 11, 0, "/a")]);
+    }
+
+    #[test]
+    fn insert_value() {
+        check(r#"{"a": , "b": 1}"#, json!({"a": null, "b": 1}), &[("insert_value", 6, 0, "/a")]);
+        check(r#"{"a":"#, json!({"a": null}), &[("insert_value", 5, 0, "/a"), ("close_container", 5, 0, "")]);
+        check("", Value::Null, &[("insert_value", 0, 0, "")]);
+    }
+
+    #[test]
+    fn insert_colon() {
+        check(r#"{"a" 1}"#, json!({"a": 1}), &[("insert_colon", 5, 0, "/a")]);
+    }
+
+    #[test]
+    fn insert_comma() {
+        check("[1 2]", json!([1, 2]), &[("insert_comma", 3, 0, "")]);
+        check(r#"{"a": 1 "b": 2}"#, json!({"a": 1, "b": 2}), &[("insert_comma", 8, 0, "")]);
+    }
+
+    #[test]
+    fn remove_comma() {
+        check("[1, 2,]", json!([1, 2]), &[("remove_comma", 5, 1, "")]);
+        check("[1,, 2]", json!([1, 2]), &[("remove_comma", 3, 1, "")]);
+        check(r#"{"a": 1,, "b": 2,}"#, json!({"a": 1, "b": 2}), &[("remove_comma", 8, 1, ""), ("remove_comma", 16, 1, "")]);
+    }
+
+    #[test]
+    fn complete_literal() {
+        check("[true, nu", json!([true, null]), &[("complete_literal", 9, 0, "/1"), ("close_container", 9, 0, "")]);
+        check(r#"{"ok": fal"#, json!({"ok": false}), &[("complete_literal", 10, 0, "/ok"), ("close_container", 10, 0, "")]);
+    }
+
+    #[test]
+    fn truncate_number() {
+        check("[1.2.3, 4]", json!([1.2, 4]), &[("truncate_number", 4, 2, "/0")]);
+        check("[-]", json!([]), &[("skip_invalid", 1, 1, "/0")]);
+    }
+
+    #[test]
+    fn invalid_escape() {
+        check(r#"["a\qb"]"#, json!(["aqb"]), &[("invalid_escape", 3, 1, "/0")]);
+        check(r#"["\u12x"]"#, json!(["\u{FFFD}12x"]), &[("invalid_escape", 2, 2, "/0")]);
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let (value, repairs) = run(b"[\"a\xFFb\"]");
+        assert_eq!(value, json!(["a\u{FFFD}b"]));
+        assert_eq!(repairs, [("invalid_utf8".to_string(), 1, 5, "/0".to_string())]);
+    }
+
+    #[test]
+    fn skip_invalid() {
+        check("[1, @@ #, 2]", json!([1, 2]), &[("skip_invalid", 4, 4, "/1")]);
+        check("{a: 1}", json!({}), &[("skip_invalid", 1, 4, "")]);
+        check("[1}]", json!([1]), &[("skip_invalid", 2, 1, "/1")]);
+    }
+
+    #[test]
+    fn skip_trailing() {
+        check(r#"{"a": 1} xyz"#, json!({"a": 1}), &[("skip_trailing", 9, 3, "")]);
+    }
+
+    #[test]
+    fn deep_nesting_is_skipped() {
+        let src = format!("{}{}", "[".repeat(MAX_DEPTH + 10), "]".repeat(MAX_DEPTH + 10));
+        let (mut value, repairs) = run(src.as_bytes());
+        assert_eq!(repairs.len(), 1);
+        assert_eq!((repairs[0].0.as_str(), repairs[0].1, repairs[0].2), ("skip_invalid", MAX_DEPTH, 20));
+        let mut depth = 0;
+        while let Value::Array(mut items) = value {
+            depth += 1;
+            value = items.pop().unwrap_or(Value::Null);
+        }
+        assert_eq!(depth, MAX_DEPTH);
+    }
+
+    #[test]
+    fn repairs_are_capped() {
+        let src = format!("[{}]", vec!["@"; MAX_REPAIRS + 500].join(", "));
+        let (value, repairs, count) = recover(src.as_bytes());
+        assert_eq!(value, json!([]));
+        assert_eq!(repairs.len(), MAX_REPAIRS);
+        assert_eq!(count, MAX_REPAIRS + 500);
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use serde_json::Value;
+use tauri::async_runtime::spawn_blocking;
+use crate::state::AppState;
+use crate::types::{Replacement, ReplaceOptions, ReplaceResult};
+use crate::tree::{search_regex, text_match_byte_ranges, child_pointer, insert_at_pointer, remove_at_pointer, split_pointer, value_at_mut};
+use crate::history::{apply_op, commit_edit, EditOp};
+
+const DEFAULT_PREVIEW_LIMIT: usize = 1000;
+
+// How matches are found and rewritten. Regex replacements expand capture groups ($1, ${name});
+// literal replacements insert the text as is.
+enum Replacer {
+    Regex(regex::Regex),
+    Literal { query: String, whole_word: bool, case_sensitive: bool },
+}
+
+impl Replacer {
+    fn replace(&self, text: &str, replacement: &str) -> Option<String> {
+        let replaced = match self {
+            Replacer::Regex(re) => {
+                if !re.is_match(text) {
+                    return None;
+                }
+                re.replace_all(text, replacement).into_owned()
+            }
+            Replacer::Literal { query, whole_word, case_sensitive } => {
+                let ranges = text_match_byte_ranges(text, query, None, *whole_word, *case_sensitive, usize::MAX);
+                if ranges.is_empty() {
+                    return None;
+                }
+                let mut out = String::with_capacity(text.len());
+                let mut last = 0;
+                for (start, end) in ranges {
+                    out.push_str(&text[last..start]);
+                    out.push_str(replacement);
+                    last = end;
+                }
+                out.push_str(&text[last..]);
+                out
+            }
+        };
+        (replaced != text).then_some(replaced)
+    }
+}
+
+struct ReplaceParams<'a> {
+    replacer: &'a Replacer,
+    replacement: &'a str,
+    search_keys: bool,
+    search_values: bool,
+    max_depth: Option<usize>,
+}
+
+// Collect replacements below `value` (at `depth` under the scope root) in document order.
+// Only object keys and string values are rewritten; numbers and booleans keep their type.
+fn collect(value: &Value, pointer: &str, depth: usize, params: &ReplaceParams, out: &mut Vec<Replacement>) -> Result<(), String> {
+    if params.max_depth.is_some_and(|max| depth + 1 > max) {
+        return Ok(());
+    }
+    match value {
+        Value::Object(map) => {
+            let mut new_keys = HashSet::new();
+            for (key, child) in map {
+                let child_ptr = child_pointer(pointer, key);
+                if params.search_keys {
+                    if let Some(new_key) = params.replacer.replace(key, params.replacement) {
+                        // Renames are applied one at a time, so the new key must not exist yet
+                        if map.contains_key(&new_key) || !new_keys.insert(new_key.clone()) {
+                            return Err(format!("Replacing key \"{key}\" at {child_ptr} would duplicate key \"{new_key}\""));
+                        }
+                        out.push(Replacement { pointer: child_ptr.clone(), target: "key".into(), before: key.clone(), after: new_key });
+                    }
+                }
+                collect_child(child, child_ptr, depth, params, out)?;
+            }
+        }
+        Value::Array(arr) => {
+            for (index, child) in arr.iter().enumerate() {
+                collect_child(child, child_pointer(pointer, &index.to_string()), depth, params, out)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn collect_child(child: &Value, pointer: String, depth: usize, params: &ReplaceParams, out: &mut Vec<Replacement>) -> Result<(), String> {
+    match child {
+        Value::String(s) if params.search_values => {
+            if let Some(after) = params.replacer.replace(s, params.replacement) {
+                out.push(Replacement { pointer, target: "value".into(), before: s.clone(), after });
+            }
+            Ok(())
+        }
+        Value::Object(_) | Value::Array(_) => collect(child, &pointer, depth + 1, params, out),
+        _ => Ok(()),
+    }
+}
+
+// Values first, while every pointer still uses the old keys; then key renames in reverse
+// document order, so children are renamed before their parents. If any step fails, the steps
+// already applied are undone so the document is never left half-edited.
+fn apply_replacements(root: &mut Value, found: &[Replacement]) -> Result<Vec<EditOp>, String> {
+    let mut ops = Vec::with_capacity(found.len());
+    if let Err(e) = apply_each(root, found, &mut ops) {
+        for op in ops.iter().rev() {
+            apply_op(root, op, true)?;
+        }
+        return Err(e);
+    }
+    Ok(ops)
+}
+
+// Each step is recorded in `ops` as soon as it changes the document
+fn apply_each(root: &mut Value, found: &[Replacement], ops: &mut Vec<EditOp>) -> Result<(), String> {
+    for r in found.iter().filter(|r| r.target == "value") {
+        let target = value_at_mut(root, &r.pointer)?;
+        let new = Value::String(r.after.clone());
+        let old = std::mem::replace(target, new.clone());
+        ops.push(EditOp::Replace { pointer: r.pointer.clone(), old, new });
+    }
+    for r in found.iter().rev().filter(|r| r.target == "key") {
+        let (parent_pointer, _) = split_pointer(&r.pointer).ok_or("Cannot rename the document root")?;
+        let new_pointer = child_pointer(parent_pointer, &r.after);
+        let (index, value) = remove_at_pointer(root, &r.pointer)?;
+        ops.push(EditOp::Remove { pointer: r.pointer.clone(), index, value: value.clone() });
+        insert_at_pointer(root, &new_pointer, index, value.clone())?;
+        ops.push(EditOp::Add { pointer: new_pointer, index, value });
+    }
+    Ok(())
+}
+
+// Find and replace across the document (or the subtree at `root_pointer`, up to `max_depth`
+// levels below it) using the search options. With `dry_run` the replacements are only listed
+// with before/after text; otherwise they are applied as a single undoable edit.
+#[tauri::command]
+pub async fn replace_all(
+    query: String,
+    replacement: String,
+    options: ReplaceOptions,
+    state: tauri::State<'_, AppState>
+) -> Result<ReplaceResult, String> {
+    let ReplaceOptions { search_keys, search_values, case_sensitive, regex, whole_word, root_pointer, max_depth, dry_run, limit } = options;
+    let snapshot = state.document(None)?;
+    if query.is_empty() {
+        return Err("Empty query".into());
+    }
+    let root_pointer = root_pointer.unwrap_or_default();
+    if snapshot.pointer(&root_pointer).is_none() {
+        return Err("Invalid pointer".into());
+    }
+    let replacer = if regex {
+        // The same regex search uses, so the preview matches what search highlights
+        let re = search_regex(&query, case_sensitive, whole_word).map_err(|e| format!("Invalid regex: {e}"))?;
+        Replacer::Regex(re)
+    } else {
+        let query = if case_sensitive { query } else { query.to_lowercase() };
+        Replacer::Literal { query, whole_word, case_sensitive }
+    };
+
+    let (snapshot, found) = spawn_blocking(move || {
+        let params = ReplaceParams {
+            replacer: &replacer,
+            replacement: &replacement,
+            search_keys,
+            search_values,
+            max_depth,
+        };
+        let mut found = Vec::new();
+        let result = match snapshot.pointer(&root_pointer) {
+            Some(scope) => collect(scope, &root_pointer, 0, &params, &mut found),
+            None => Err("Invalid pointer".into()),
+        };
+        (snapshot, result.map(|_| found))
+    })
+    .await
+    .map_err(|e| format!("Join error: {e}"))?;
+    let mut found = found?;
+
+    let limit = limit.unwrap_or(DEFAULT_PREVIEW_LIMIT);
+    let total_count = found.len();
+    if dry_run.unwrap_or(false) || found.is_empty() {
+        found.truncate(limit);
+        return Ok(ReplaceResult { replacements: found, total_count, applied: false });
+    }
+
+    let mut guard = state.doc_mut();
+    let Some(root_arc) = &mut *guard else { return Err(state.missing_document()); };
+    if !Arc::ptr_eq(root_arc, &snapshot) {
+        return Err("Document changed while replacing; please retry".into());
+    }
+    drop(snapshot); // release our reference so make_mut edits in place instead of cloning
+    let root_mut: &mut Value = Arc::make_mut(root_arc);
+
+    let ops = apply_replacements(root_mut, &found)?;
+    commit_edit(&state, "Replace all", ops);
+
+    found.truncate(limit);
+    Ok(ReplaceResult { replacements: found, total_count, applied: true })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn replacement(pointer: &str, target: &str, before: &str, after: &str) -> Replacement {
+        Replacement { pointer: pointer.into(), target: target.into(), before: before.into(), after: after.into() }
+    }
+
+    #[test]
+    fn applies_values_then_renames_children_before_parents() {
+        let mut doc = json!({"old": {"old": "old value"}});
+        let found = vec![
+            replacement("/old", "key", "old", "new"),
+            replacement("/old/old", "key", "old", "new"),
+            replacement("/old/old", "value", "old value", "new value"),
+        ];
+        let ops = apply_replacements(&mut doc, &found).unwrap();
+        assert_eq!(doc, json!({"new": {"new": "new value"}}));
+        assert_eq!(ops.len(), 5);
+    }
+
+    #[test]
+    fn failed_replacement_leaves_the_document_unchanged() {
+        let original = json!({"a": "x", "b": {"c": "x"}});
+        let mut doc = original.clone();
+        let found = vec![
+            replacement("/a", "value", "x", "y"),
+            replacement("/b/c", "value", "x", "y"),
+            replacement("/b/c", "key", "c", "d"),
+            replacement("/missing", "key", "missing", "found"),
+        ];
+        assert!(apply_replacements(&mut doc, &found).is_err());
+        assert_eq!(doc, original);
+    }
+}
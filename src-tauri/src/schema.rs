@@ -0,0 +1,324 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use serde_json::{json, Map, Value};
+use tauri::async_runtime::spawn_blocking;
+use crate::state::{AppState, MainDocument};
+use crate::document::{JsonKind, JsonNode};
+use crate::types::{Node, SchemaError, ValidationReport};
+use crate::tree::{list_children, mark_with_ancestors};
+use crate::filter::parse_iso_datetime;
+use crate::file::write_atomically;
+
+const MAX_SCHEMA_ERRORS: usize = 1000;
+// Inferred string enums: at most this many distinct values, each seen twice on average
+const ENUM_MAX_VALUES: usize = 10;
+const INFERRED_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+// Per-pointer result of the last validation, for flagging nodes in the tree
+pub struct SchemaValidation {
+    statuses: HashMap<String, &'static str>,
+}
+
+impl SchemaValidation {
+    pub fn annotate(&self, nodes: &mut [Node]) {
+        for node in nodes {
+            node.validation = self.statuses.get(&node.pointer).map(|s| s.to_string());
+        }
+    }
+}
+
+fn parse_draft(name: &str) -> Result<jsonschema::Draft, String> {
+    match name {
+        "7" | "draft7" | "draft-07" => Ok(jsonschema::Draft::Draft7),
+        "2020-12" | "draft2020-12" => Ok(jsonschema::Draft::Draft202012),
+        other => Err(format!("Unsupported schema draft: {other} (expected \"7\" or \"2020-12\")")),
+    }
+}
+
+// Validate the loaded document against the JSON Schema file at `schema_path`. The draft comes from
+// `draft` ("7" or "2020-12") or else the schema's `$schema`. Relative `$ref`s resolve against the
+// schema file's location on disk. Invalid nodes (and their ancestors) are flagged through
+// load_children until the document changes.
+#[tauri::command]
+pub async fn validate_against_schema(schema_path: String, draft: Option<String>, state: tauri::State<'_, AppState>) -> Result<ValidationReport, String> {
+    let doc = state.main_document()?;
+    let draft = draft.as_deref().map(parse_draft).transpose()?;
+
+    let (doc, errors, error_count, statuses) = spawn_blocking(move || -> Result<_, String> {
+        let text = std::fs::read_to_string(&schema_path).map_err(|e| format!("Failed to read schema: {e}"))?;
+        let schema: Value = serde_json::from_str(&text).map_err(|e| format!("Schema is not valid JSON: {e}"))?;
+        let absolute = std::fs::canonicalize(&schema_path).map_err(|e| e.to_string())?;
+        let base_uri = tauri::Url::from_file_path(&absolute).map_err(|_| "Invalid schema path".to_string())?;
+
+        let mut options = jsonschema::options().with_base_uri(base_uri.to_string());
+        if let Some(draft) = draft {
+            options = options.with_draft(draft);
+        }
+        let validator = options.build(&schema).map_err(|e| format!("Invalid schema: {e}"))?;
+
+        let mut errors = Vec::new();
+        let mut error_count = 0;
+        let mut statuses = HashMap::new();
+        let value = doc.to_value();
+        for error in validator.iter_errors(&value) {
+            error_count += 1;
+            let pointer = error.instance_path().as_str();
+            mark_with_ancestors(&mut statuses, pointer, "invalid", "contains_invalid");
+            if errors.len() < MAX_SCHEMA_ERRORS {
+                errors.push(SchemaError {
+                    pointer: pointer.to_string(),
+                    schema_path: error.schema_path().as_str().to_string(),
+                    message: error.to_string(),
+                });
+            }
+        }
+        Ok((doc, errors, error_count, statuses))
+    })
+    .await
+    .map_err(|e| format!("Join error: {e}"))??;
+
+    // Skip storing flags if an edit landed while validating; they would point at stale nodes
+    if doc.is_current(&state) {
+        *state.schema_validation.write() = Some(Arc::new(SchemaValidation { statuses }));
+    }
+    Ok(ValidationReport { valid: error_count == 0, error_count, errors })
+}
+
+// Accumulated shape of every value seen at one position in the document (e.g. all elements of
+// an array, or one key across all objects that have it)
+#[derive(Default)]
+struct Shape {
+    null: bool,
+    boolean: bool,
+    integer: bool,
+    integer_range: Option<(i128, i128)>, // smallest and largest integer seen (saturating)
+    number: bool,                      // non-integer numbers
+    string: Option<StringShape>,
+    object: Option<ObjectShape>,
+    items: Option<Box<Shape>>,         // merged shape of the elements of every array seen here
+}
+
+struct StringShape {
+    count: usize,
+    values: Option<Vec<String>>,       // distinct values, while few enough for an enum
+    format: Option<&'static str>,      // format every value so far satisfies
+}
+
+#[derive(Default)]
+struct ObjectShape {
+    count: usize,
+    fields: Vec<(String, usize, Shape)>, // key, number of objects having it, merged value shape
+    index: HashMap<String, usize>,
+}
+
+impl Shape {
+    fn add<'a>(&mut self, value: impl JsonNode<'a>) {
+        match value.kind() {
+            JsonKind::Null => self.null = true,
+            JsonKind::Bool(_) => self.boolean = true,
+            JsonKind::Number(n) if n.is_f64() => self.number = true,
+            JsonKind::Number(n) => {
+                self.integer = true;
+                let text = n.as_str();
+                let v = text.parse::<i128>().unwrap_or(if text.starts_with('-') { i128::MIN } else { i128::MAX });
+                let (min, max) = self.integer_range.get_or_insert((v, v));
+                *min = (*min).min(v);
+                *max = (*max).max(v);
+            }
+            JsonKind::String(s) => {
+                let shape = match &mut self.string {
+                    Some(shape) => {
+                        if shape.format.is_some() && shape.format != detect_format(s) {
+                            shape.format = None;
+                        }
+                        shape
+                    }
+                    None => self.string.insert(StringShape { count: 0, values: Some(vec![]), format: detect_format(s) }),
+                };
+                shape.count += 1;
+                if let Some(values) = &mut shape.values {
+                    if !values.iter().any(|v| v == s) {
+                        values.push(s.to_string());
+                    }
+                    if values.len() > ENUM_MAX_VALUES {
+                        shape.values = None;
+                    }
+                }
+            }
+            JsonKind::Array(_) => {
+                let shape = self.items.get_or_insert_with(Box::default);
+                for (_, item) in value.children() {
+                    shape.add(item);
+                }
+            }
+            JsonKind::Object(_) => {
+                let object = self.object.get_or_insert_with(ObjectShape::default);
+                object.count += 1;
+                for (key, child) in value.children() {
+                    let i = *object.index.entry(key.to_string()).or_insert_with(|| {
+                        object.fields.push((key.to_string(), 0, Shape::default()));
+                        object.fields.len() - 1
+                    });
+                    object.fields[i].1 += 1;
+                    object.fields[i].2.add(child);
+                }
+            }
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        !(self.null || self.boolean || self.integer || self.number)
+            && self.string.is_none() && self.object.is_none() && self.items.is_none()
+    }
+
+    // `integer_widths` adds an OpenAPI-style `format` to integer-only positions (see
+    // infer_with_integer_widths)
+    fn to_schema(&self, integer_widths: bool) -> Value {
+        let mut types = Vec::new();
+        if self.object.is_some() { types.push("object"); }
+        if self.items.is_some() { types.push("array"); }
+        if self.string.is_some() { types.push("string"); }
+        if self.number { types.push("number"); } else if self.integer { types.push("integer"); }
+        if self.boolean { types.push("boolean"); }
+        if self.null { types.push("null"); }
+
+        let mut schema = Map::new();
+        match types.as_slice() {
+            [] => {} // never saw a value here (e.g. only empty arrays): accept anything
+            [single] => { schema.insert("type".into(), json!(single)); }
+            _ => { schema.insert("type".into(), json!(types)); }
+        }
+        if let Some(object) = &self.object {
+            let properties: Map<String, Value> = object.fields.iter()
+                .map(|(key, _, shape)| (key.clone(), shape.to_schema(integer_widths)))
+                .collect();
+            // Keys present in every object are required; the rest are optional
+            let required: Vec<&str> = object.fields.iter()
+                .filter(|(_, seen, _)| *seen == object.count)
+                .map(|(key, _, _)| key.as_str())
+                .collect();
+            schema.insert("properties".into(), Value::Object(properties));
+            if !required.is_empty() {
+                schema.insert("required".into(), json!(required));
+            }
+        }
+        // Width of the integers seen, so generated types can hold all of them; none when some
+        // don't fit 64 bits
+        if let (true, Some((min, max)), false, None) = (integer_widths, self.integer_range, self.number, &self.string) {
+            if min >= i64::MIN.into() && max <= i64::MAX.into() {
+                schema.insert("format".into(), json!("int64"));
+            } else if min >= 0 && max <= u64::MAX.into() {
+                schema.insert("format".into(), json!("uint64"));
+            }
+        }
+        if let Some(items) = self.items.as_deref().filter(|items| !items.is_empty()) {
+            schema.insert("items".into(), items.to_schema(integer_widths));
+        }
+        if let Some(string) = &self.string {
+            let only_strings = types.iter().all(|t| *t == "string" || *t == "null");
+            match (string.format, &string.values) {
+                (Some(format), _) => { schema.insert("format".into(), json!(format)); }
+                (None, Some(values)) if only_strings && string.count >= 2 * values.len() => {
+                    let mut options: Vec<Value> = values.iter().map(|v| json!(v)).collect();
+                    if self.null {
+                        options.push(Value::Null);
+                    }
+                    schema.insert("enum".into(), Value::Array(options));
+                }
+                _ => {}
+            }
+        }
+        Value::Object(schema)
+    }
+}
+
+// Schema (without "$schema") describing `value`, with the shapes of array elements merged
+pub fn infer<'a>(value: impl JsonNode<'a>) -> Value {
+    let mut shape = Shape::default();
+    shape.add(value);
+    shape.to_schema(false)
+}
+
+// Like `infer`, but integer-only positions also get "format": "int64" or "uint64" when every
+// integer seen fits. For choosing Rust integer types; not part of the user-visible schema.
+pub fn infer_with_integer_widths<'a>(value: impl JsonNode<'a>) -> Value {
+    let mut shape = Shape::default();
+    shape.add(value);
+    shape.to_schema(true)
+}
+
+fn detect_format(s: &str) -> Option<&'static str> {
+    if parse_iso_datetime(s).is_some() {
+        return Some(if s.len() == 10 { "date" } else { "date-time" });
+    }
+    if is_uuid(s) {
+        Some("uuid")
+    } else if is_email(s) {
+        Some("email")
+    } else if is_uri(s) {
+        Some("uri")
+    } else {
+        None
+    }
+}
+
+fn is_uuid(s: &str) -> bool {
+    let b = s.as_bytes();
+    b.len() == 36 && b.iter().enumerate().all(|(i, c)| match i {
+        8 | 13 | 18 | 23 => *c == b'-',
+        _ => c.is_ascii_hexdigit(),
+    })
+}
+
+fn is_email(s: &str) -> bool {
+    match s.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty() && !domain.contains('@') && !s.contains(char::is_whitespace)
+                && domain.split('.').count() >= 2 && domain.split('.').all(|part| !part.is_empty())
+        }
+        None => false,
+    }
+}
+
+// Absolute URIs with an authority, e.g. https://host/path
+fn is_uri(s: &str) -> bool {
+    match s.split_once("://") {
+        Some((scheme, rest)) => {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+                && !rest.is_empty() && !s.contains(char::is_whitespace)
+        }
+        None => false,
+    }
+}
+
+// Infer a JSON Schema (2020-12) describing the value at `pointer` (default: the whole document).
+// The schema becomes the "result" document (browse with load_children source "result") and is
+// also written to `path` when given. Returns the schema's top-level nodes.
+#[tauri::command]
+pub async fn infer_schema(pointer: Option<String>, path: Option<String>, state: tauri::State<'_, AppState>) -> Result<Vec<Node>, String> {
+    let doc = state.main_document()?;
+    let pointer = pointer.unwrap_or_default();
+    let schema = spawn_blocking(move || -> Result<Value, String> {
+        let mut schema = match &doc {
+            MainDocument::Tree(doc) => doc.pointer(&pointer).map(infer),
+            MainDocument::Compact(doc) => doc.root().pointer(&pointer).map(infer),
+        }
+        .ok_or("Invalid pointer")?;
+        if let Value::Object(map) = &mut schema {
+            map.insert("$schema".into(), json!(INFERRED_DIALECT));
+        }
+        Ok(schema)
+    })
+    .await
+    .map_err(|e| format!("Join error: {e}"))??;
+
+    if let Some(path) = path {
+        write_atomically(Path::new(&path), &schema, "json", 2, false)?;
+    }
+    let arc = Arc::new(schema);
+    let top = list_children(arc.as_ref(), "", 0, 100);
+    *state.result_doc.write() = Some(arc);
+    Ok(top)
+}
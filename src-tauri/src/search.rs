@@ -1,8 +1,72 @@
-use serde_json::Value;
+use std::sync::{Arc, atomic::{AtomicU64, Ordering}};
+use rayon::prelude::*;
+use crate::document::{JsonChild, JsonNode};
 use tauri::{async_runtime::spawn_blocking, Emitter};
-use crate::state::AppState;
-use crate::types::{SearchResult, SearchResponse};
-use crate::tree::{text_matches, to_node_with_truncation, create_node_for_path, escape_pointer_token};
+use crate::state::{AppState, MainDocument};
+use crate::types::{SearchResult, SearchResponse, MatchRange, MatchSnippet};
+use crate::tree::{search_regex, text_matches, text_match_ranges, match_snippet, to_node_with_truncation, create_node_for_path, child_pointer};
+use crate::filter::StructuredFilter;
+
+// Containers with at least this many children are searched in parallel, in chunks of
+// PARALLEL_CHUNK children. Chunk results are concatenated in order, so the output is in
+// document order regardless of scheduling.
+const PARALLEL_THRESHOLD: usize = 1024;
+const PARALLEL_CHUNK: usize = 256;
+// Streaming search processes this many sibling subtrees per parallel window before emitting
+const STREAM_WINDOW: usize = 4096;
+
+// Everything the traversal needs to decide whether a node matches
+pub struct SearchParams<'a> {
+    pub query: &'a str,           // already lowercased unless case_sensitive
+    pub re: Option<&'a regex::Regex>, // from search_regex; matched against the original text
+    pub search_keys: bool,
+    pub search_values: bool,
+    pub search_paths: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub filter: Option<&'a StructuredFilter>, // structured mode replaces text matching entirely
+    pub token: Option<&'a SearchToken>,       // streaming searches stop early once cancelled
+    pub max_depth: Option<usize>,             // levels below the search root to visit; None = unlimited
+}
+
+// Identifies a streaming search. It is abandoned when a newer search starts
+// (`active_search_id` moves on) or when `cancel_search` is called with its id.
+pub struct SearchToken {
+    id: u64,
+    active: Arc<AtomicU64>,
+    cancelled: Arc<AtomicU64>,
+}
+
+impl SearchToken {
+    fn is_cancelled(&self) -> bool {
+        self.active.load(Ordering::Relaxed) != self.id || self.cancelled.load(Ordering::Relaxed) >= self.id
+    }
+}
+
+impl SearchParams<'_> {
+    fn cancelled(&self) -> bool {
+        self.token.is_some_and(SearchToken::is_cancelled)
+    }
+
+    fn within_depth(&self, depth: usize) -> bool {
+        self.max_depth.is_none_or(|max| depth <= max)
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        if self.case_sensitive || self.re.is_some() {
+            text_matches(text, self.query, self.re, self.whole_word)
+        } else {
+            text_matches(&text.to_lowercase(), self.query, self.re, self.whole_word)
+        }
+    }
+
+    // Highlight ranges (and a snippet for long text) for text that `matches` accepted
+    fn highlight(&self, text: &str) -> (Vec<MatchRange>, Option<MatchSnippet>) {
+        let ranges = text_match_ranges(text, self.query, self.re, self.whole_word, self.case_sensitive);
+        let snippet = match_snippet(text, &ranges);
+        (ranges, snippet)
+    }
+}
 
 #[tauri::command]
 pub async fn search(
@@ -15,44 +79,51 @@ pub async fn search(
     whole_word: bool,
     offset: usize,
     limit: usize,
+    structured: Option<bool>,
+    root_pointer: Option<String>,
+    max_depth: Option<usize>,
     state: tauri::State<'_, AppState>
 ) -> Result<SearchResponse, String> {
-    // Limit scope of read guard so it's dropped before await (RwLock guard is not Send)
-    let root_arc = {
-        let guard = state.doc.read();
-        let Some(root) = &*guard else { return Err("No document loaded".into()); };
-        root.clone()
-    }; // guard dropped here
+    let root_doc = state.main_document()?;
+    // Scope: traversal starts at this node; result pointers stay absolute
+    let root_pointer = root_pointer.unwrap_or_default();
+    if !root_doc.has_pointer(&root_pointer) {
+        return Err("Invalid pointer".into());
+    }
 
     if query.trim().is_empty() {
         return Ok(SearchResponse { results: vec![], total_count: 0, has_more: false });
     }
 
+    // Structured mode: the query is a filter expression (see filter.rs) evaluated on typed values
+    let structured_filter = if structured.unwrap_or(false) {
+        Some(StructuredFilter::parse(&query, case_sensitive)?)
+    } else {
+        None
+    };
+
     let search_query_owned = if case_sensitive { query.clone() } else { query.to_lowercase() };
-    let regex_enable = regex;
-    let whole_word_flag = whole_word;
-    let case_sensitive_flag = case_sensitive;
-    let search_keys_flag = search_keys;
-    let search_values_flag = search_values;
-    let search_paths_flag = search_paths;
-    let query_clone_for_regex = query.clone();
+    let re = if regex { search_regex(&query, case_sensitive, whole_word).ok() } else { None };
 
     // Offload CPU intensive traversal
     let (all_results, total_count) = spawn_blocking(move || {
-        let re = if regex_enable { regex::Regex::new(&query_clone_for_regex).ok() } else { None };
+        let params = SearchParams {
+            query: &search_query_owned,
+            re: re.as_ref(),
+            search_keys,
+            search_values,
+            search_paths,
+            case_sensitive,
+            whole_word,
+            filter: structured_filter.as_ref(),
+            token: None,
+            max_depth,
+        };
         let mut collected = Vec::new();
-        search_recursive(
-            &root_arc,
-            "",
-            &search_query_owned,
-            re.as_ref(),
-            search_keys_flag,
-            search_values_flag,
-            search_paths_flag,
-            case_sensitive_flag,
-            whole_word_flag,
-            &mut collected,
-        );
+        match &root_doc {
+            MainDocument::Tree(doc) => search_scope(doc.as_ref(), &root_pointer, &params, &mut collected),
+            MainDocument::Compact(doc) => search_scope(doc.root(), &root_pointer, &params, &mut collected),
+        }
         let total = collected.len();
         (collected, total)
     })
@@ -69,10 +140,63 @@ pub async fn search(
     Ok(SearchResponse { results, total_count, has_more })
 }
 
+// Collects results and emits them as "search_batch" events of `batch_size`
+struct BatchEmitter<'t> {
+    id: u64,
+    batch_size: usize,
+    batch: Vec<SearchResult>,
+    total_so_far: usize,
+    start_instant: std::time::Instant,
+    handle: tauri::AppHandle,
+    token: &'t SearchToken,
+    cancelled: bool,
+}
+
+impl BatchEmitter<'_> {
+    // Latches once the search is superseded or cancelled
+    fn is_cancelled(&mut self) -> bool {
+        self.cancelled = self.cancelled || self.token.is_cancelled();
+        self.cancelled
+    }
+
+    fn extend(&mut self, results: Vec<SearchResult>) {
+        // Results gathered after cancellation would be stale; drop them
+        if self.is_cancelled() {
+            return;
+        }
+        for result in results {
+            self.batch.push(result);
+            if self.batch.len() >= self.batch_size {
+                self.flush();
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.is_cancelled() {
+            self.batch.clear();
+            return;
+        }
+        if self.batch.is_empty() {
+            return;
+        }
+        self.total_so_far += self.batch.len();
+        let batch = std::mem::replace(&mut self.batch, Vec::with_capacity(self.batch_size));
+        let _ = self.handle.emit("search_batch", serde_json::json!({
+            "id": self.id,
+            "batch": batch,
+            "total_so_far": self.total_so_far,
+            "elapsed_ms": self.start_instant.elapsed().as_millis()
+        }));
+    }
+}
+
 // Streaming search: emits incremental batches so UI can render partial results.
+// Results arrive in document order, the same as `search` returns them.
 // Events:
 //  - "search_batch" { id, batch: [SearchResult], total_so_far, elapsed_ms }
-//  - "search_done" { id, total, elapsed_ms }
+//  - "search_done" { id, total, elapsed_ms, status: "finished" | "cancelled" }
+// Starting a new search abandons any older one still running; `cancel_search` stops one explicitly.
 #[tauri::command]
 pub async fn search_stream(
     query: String,
@@ -82,391 +206,356 @@ pub async fn search_stream(
     case_sensitive: bool,
     regex: bool,
     whole_word: bool,
+    structured: Option<bool>,
+    root_pointer: Option<String>,
+    max_depth: Option<usize>,
     app_handle: tauri::AppHandle,
     state: tauri::State<'_, AppState>
 ) -> Result<u64, String> {
-    let root_arc = {
-        let guard = state.doc.read();
-        let Some(root) = &*guard else { return Err("No document loaded".into()); };
-        root.clone()
-    };
+    let root_doc = state.main_document()?;
     if query.trim().is_empty() { return Err("Empty query".into()); }
+    let root_pointer = root_pointer.unwrap_or_default();
+    if !root_doc.has_pointer(&root_pointer) {
+        return Err("Invalid pointer".into());
+    }
 
-    let case_sensitive_flag = case_sensitive;
-    let query_norm = if case_sensitive_flag { query.clone() } else { query.to_lowercase() };
-    let re_opt = if regex { regex::Regex::new(&query).ok() } else { None };
+    let query_norm = if case_sensitive { query.clone() } else { query.to_lowercase() };
+    let re_opt = if regex { search_regex(&query, case_sensitive, whole_word).ok() } else { None };
+    let structured_filter = if structured.unwrap_or(false) {
+        Some(StructuredFilter::parse(&query, case_sensitive)?)
+    } else {
+        None
+    };
     let batch_size: usize = 10; // default batch size
 
-    let id = state.active_search_id.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+    let id = state.active_search_id.fetch_add(1, Ordering::SeqCst) + 1;
+    let token = SearchToken {
+        id,
+        active: state.active_search_id.clone(),
+        cancelled: state.cancelled_search_id.clone(),
+    };
     let handle_clone = app_handle.clone();
 
     spawn_blocking(move || {
-        let mut stack: Vec<(&Value, String)> = vec![(root_arc.as_ref(), String::from(""))];
-        let mut total_so_far: usize = 0;
-        let start_instant = std::time::Instant::now();
-        let mut batch: Vec<SearchResult> = Vec::with_capacity(batch_size);
-
-        while let Some((value, pointer)) = stack.pop() {
-            // path match
-            if search_paths {
-                let path_check = if case_sensitive_flag { pointer.clone() } else { pointer.to_lowercase() };
-                let path_match = if let Some(re) = &re_opt { 
-                    re.is_match(&path_check) 
-                } else if whole_word { 
-                    path_check.split(|c: char| !c.is_alphanumeric()).any(|w| w == query_norm) 
-                } else { 
-                    path_check.contains(&query_norm) 
-                };
-                if path_match {
-                    batch.push(SearchResult {
-                        node: create_node_for_path(value, &pointer),
-                        match_type: "path".into(),
-                        match_text: pointer.clone(),
-                        context: None,
-                    });
-                }
-            }
-            match value {
-                Value::Object(map) => {
-                    for (k, v) in map.iter() {
-                        if search_keys {
-                            let key_check = if case_sensitive_flag { k.to_string() } else { k.to_lowercase() };
-                            let key_match = if let Some(re) = &re_opt { 
-                                re.is_match(&key_check) 
-                            } else if whole_word { 
-                                key_check.split(|c: char| !c.is_alphanumeric()).any(|w| w == query_norm) 
-                            } else { 
-                                key_check.contains(&query_norm) 
-                            };
-                            if key_match {
-                                batch.push(SearchResult {
-                                    node: to_node_with_truncation(&pointer, Some(k), v, None),
-                                    match_type: "key".into(),
-                                    match_text: k.clone(),
-                                    context: None,
-                                });
-                            }
-                        }
-                        if search_values {
-                            match v {
-                                Value::String(s) => {
-                                    let check = if case_sensitive_flag { s.clone() } else { s.to_lowercase() };
-                                    let is_match = if let Some(re) = &re_opt { 
-                                        re.is_match(&check) 
-                                    } else if whole_word { 
-                                        check.split(|c: char| !c.is_alphanumeric()).any(|w| w == query_norm) 
-                                    } else { 
-                                        check.contains(&query_norm) 
-                                    };
-                                    if is_match {
-                                        batch.push(SearchResult { 
-                                            node: to_node_with_truncation(&pointer, Some(k), v, None), 
-                                            match_type: "value".into(), 
-                                            match_text: s.clone(), 
-                                            context: Some(format!("in key: {}", k)) 
-                                        });
-                                    }
-                                }
-                                Value::Number(n) => {
-                                    let num_str = n.to_string();
-                                    let check = if case_sensitive_flag { num_str.clone() } else { num_str.to_lowercase() };
-                                    let is_match = if let Some(re) = &re_opt { 
-                                        re.is_match(&check) 
-                                    } else if whole_word { 
-                                        check.split(|c: char| !c.is_alphanumeric()).any(|w| w == query_norm) 
-                                    } else { 
-                                        check.contains(&query_norm) 
-                                    };
-                                    if is_match { 
-                                        batch.push(SearchResult { 
-                                            node: to_node_with_truncation(&pointer, Some(k), v, None), 
-                                            match_type: "value".into(), 
-                                            match_text: num_str, 
-                                            context: Some(format!("in key: {}", k)) 
-                                        }); 
-                                    }
-                                }
-                                Value::Bool(b) => {
-                                    let bool_str = b.to_string();
-                                    let check = if case_sensitive_flag { bool_str.clone() } else { bool_str.to_lowercase() };
-                                    let is_match = if let Some(re) = &re_opt { 
-                                        re.is_match(&check) 
-                                    } else if whole_word { 
-                                        check.split(|c: char| !c.is_alphanumeric()).any(|w| w == query_norm) 
-                                    } else { 
-                                        check.contains(&query_norm) 
-                                    };
-                                    if is_match { 
-                                        batch.push(SearchResult { 
-                                            node: to_node_with_truncation(&pointer, Some(k), v, None), 
-                                            match_type: "value".into(), 
-                                            match_text: bool_str, 
-                                            context: Some(format!("in key: {}", k)) 
-                                        }); 
-                                    }
-                                }
-                                _ => {}
-                            }
-                        }
-                        if matches!(v, Value::Object(_) | Value::Array(_)) {
-                            let child_pointer = if pointer.is_empty() { 
-                                format!("/{}", escape_pointer_token(k)) 
-                            } else { 
-                                format!("{}/{}", pointer, escape_pointer_token(k)) 
-                            };
-                            stack.push((v, child_pointer));
-                        }
-                        if batch.len() >= batch_size {
-                            total_so_far += batch.len();
-                            let _ = handle_clone.emit("search_batch", serde_json::json!({ 
-                                "id": id, 
-                                "batch": batch, 
-                                "total_so_far": total_so_far, 
-                                "elapsed_ms": start_instant.elapsed().as_millis() 
-                            }));
-                            batch = Vec::with_capacity(batch_size);
-                        }
-                    }
-                }
-                Value::Array(arr) => {
-                    for (idx, item) in arr.iter().enumerate() {
-                        if search_values {
-                            match item {
-                                Value::String(s) => {
-                                    let check = if case_sensitive_flag { s.clone() } else { s.to_lowercase() };
-                                    let is_match = if let Some(re) = &re_opt { 
-                                        re.is_match(&check) 
-                                    } else if whole_word { 
-                                        check.split(|c: char| !c.is_alphanumeric()).any(|w| w == query_norm) 
-                                    } else { 
-                                        check.contains(&query_norm) 
-                                    };
-                                    if is_match { 
-                                        batch.push(SearchResult { 
-                                            node: to_node_with_truncation(&pointer, Some(&idx.to_string()), item, None), 
-                                            match_type: "value".into(), 
-                                            match_text: s.clone(), 
-                                            context: Some(format!("in index: {}", idx)) 
-                                        }); 
-                                    }
-                                }
-                                Value::Number(n) => {
-                                    let num_str = n.to_string();
-                                    let check = if case_sensitive_flag { num_str.clone() } else { num_str.to_lowercase() };
-                                    let is_match = if let Some(re) = &re_opt { 
-                                        re.is_match(&check) 
-                                    } else if whole_word { 
-                                        check.split(|c: char| !c.is_alphanumeric()).any(|w| w == query_norm) 
-                                    } else { 
-                                        check.contains(&query_norm) 
-                                    };
-                                    if is_match { 
-                                        batch.push(SearchResult { 
-                                            node: to_node_with_truncation(&pointer, Some(&idx.to_string()), item, None), 
-                                            match_type: "value".into(), 
-                                            match_text: num_str, 
-                                            context: Some(format!("in index: {}", idx)) 
-                                        }); 
-                                    }
-                                }
-                                Value::Bool(b) => {
-                                    let bool_str = b.to_string();
-                                    let check = if case_sensitive_flag { bool_str.clone() } else { bool_str.to_lowercase() };
-                                    let is_match = if let Some(re) = &re_opt { 
-                                        re.is_match(&check) 
-                                    } else if whole_word { 
-                                        check.split(|c: char| !c.is_alphanumeric()).any(|w| w == query_norm) 
-                                    } else { 
-                                        check.contains(&query_norm) 
-                                    };
-                                    if is_match { 
-                                        batch.push(SearchResult { 
-                                            node: to_node_with_truncation(&pointer, Some(&idx.to_string()), item, None), 
-                                            match_type: "value".into(), 
-                                            match_text: bool_str, 
-                                            context: Some(format!("in index: {}", idx)) 
-                                        }); 
-                                    }
-                                }
-                                _ => {}
-                            }
-                        }
-                        if matches!(item, Value::Object(_) | Value::Array(_)) {
-                            let child_pointer = format!("{}/{}", pointer, idx);
-                            stack.push((item, child_pointer));
-                        }
-                        if batch.len() >= batch_size {
-                            total_so_far += batch.len();
-                            let _ = handle_clone.emit("search_batch", serde_json::json!({ 
-                                "id": id, 
-                                "batch": batch, 
-                                "total_so_far": total_so_far, 
-                                "elapsed_ms": start_instant.elapsed().as_millis() 
-                            }));
-                            batch = Vec::with_capacity(batch_size);
-                        }
-                    }
-                }
-                _ => {}
-            }
-        }
-        if !batch.is_empty() {
-            total_so_far += batch.len();
-            let _ = handle_clone.emit("search_batch", serde_json::json!({ 
-                "id": id, 
-                "batch": batch, 
-                "total_so_far": total_so_far, 
-                "elapsed_ms": start_instant.elapsed().as_millis() 
-            }));
+        let params = SearchParams {
+            query: &query_norm,
+            re: re_opt.as_ref(),
+            search_keys,
+            search_values,
+            search_paths,
+            case_sensitive,
+            whole_word,
+            filter: structured_filter.as_ref(),
+            token: Some(&token),
+            max_depth,
+        };
+        let mut emitter = BatchEmitter {
+            id,
+            batch_size,
+            batch: Vec::with_capacity(batch_size),
+            total_so_far: 0,
+            start_instant: std::time::Instant::now(),
+            handle: handle_clone,
+            token: &token,
+            cancelled: false,
+        };
+
+        match &root_doc {
+            MainDocument::Tree(doc) => stream_scope(doc.as_ref(), &root_pointer, &params, &mut emitter),
+            MainDocument::Compact(doc) => stream_scope(doc.root(), &root_pointer, &params, &mut emitter),
         }
-        let _ = handle_clone.emit("search_done", serde_json::json!({ 
-            "id": id, 
-            "total": total_so_far, 
-            "elapsed_ms": start_instant.elapsed().as_millis() 
+        emitter.flush();
+
+        let _ = emitter.handle.emit("search_done", serde_json::json!({
+            "id": id,
+            "total": emitter.total_so_far,
+            "elapsed_ms": emitter.start_instant.elapsed().as_millis(),
+            "status": if emitter.cancelled { "cancelled" } else { "finished" }
         }));
     });
 
     Ok(id)
 }
 
-pub fn search_recursive(
-    value: &Value,
-    current_pointer: &str,
-    query: &str,
-    re: Option<&regex::Regex>,
-    search_keys: bool,
-    search_values: bool,
-    search_paths: bool,
-    case_sensitive: bool,
-    whole_word: bool,
-    results: &mut Vec<SearchResult>,
-) {
-    // Search in the current path if enabled
-    if search_paths {
-        let path_to_check = if case_sensitive { 
-            current_pointer.to_string() 
-        } else { 
-            current_pointer.to_lowercase() 
-        };
-        let matches = text_matches(&path_to_check, query, re, whole_word);
-        if matches {
-            let node = create_node_for_path(value, current_pointer);
+// Stop a running streaming search. Its "search_done" event reports status "cancelled";
+// ids that already finished are unaffected. Ids that were never handed out are rejected, since
+// cancelling them would cancel searches that have not started yet.
+#[tauri::command]
+pub fn cancel_search(id: u64, state: tauri::State<'_, AppState>) -> Result<(), String> {
+    if id > state.active_search_id.load(Ordering::SeqCst) {
+        return Err(format!("Unknown search id: {id}"));
+    }
+    state.cancelled_search_id.fetch_max(id, Ordering::SeqCst);
+    Ok(())
+}
+
+// Streaming search of the node at `pointer` (the search root) and everything below it
+fn stream_scope<'a>(root: impl JsonNode<'a>, pointer: &str, params: &SearchParams, emitter: &mut BatchEmitter) {
+    if let Some(scope) = root.pointer(pointer) {
+        let mut root_matches = Vec::new();
+        check_path(scope, pointer, params, &mut root_matches);
+        emitter.extend(root_matches);
+        stream_children(scope, pointer, 0, params, emitter);
+    }
+}
+
+// Collecting search of the node at `pointer` and everything below it
+fn search_scope<'a>(root: impl JsonNode<'a>, pointer: &str, params: &SearchParams, results: &mut Vec<SearchResult>) {
+    if let Some(scope) = root.pointer(pointer) {
+        search_recursive(scope, pointer, params, results);
+    }
+}
+
+// Stream the children of `value` in document order. Small siblings are searched in parallel
+// windows; a child that is itself a large container is descended into so its results
+// stream out progressively instead of arriving in one piece at the end.
+// `depth` is the depth of `value` below the search root.
+fn stream_children<'a, N: JsonNode<'a>>(value: N, pointer: &str, depth: usize, params: &SearchParams, emitter: &mut BatchEmitter) {
+    if !params.within_depth(depth + 1) {
+        return;
+    }
+    let is_array = value.is_array();
+    let mut window: Vec<JsonChild<'a, N>> = Vec::new();
+    for (key, child) in value.children() {
+        if emitter.is_cancelled() {
+            return;
+        }
+        if child.child_count() < PARALLEL_THRESHOLD {
+            window.push((key, child));
+            if window.len() >= STREAM_WINDOW {
+                emitter.extend(search_window(&window, pointer, is_array, depth + 1, params));
+                window.clear();
+            }
+            continue;
+        }
+        emitter.extend(search_window(&window, pointer, is_array, depth + 1, params));
+        window.clear();
+
+        let own_pointer = child_pointer(pointer, &key);
+        let mut own = Vec::new();
+        check_child(pointer, &key, Some(&own_pointer), child, is_array, params, &mut own);
+        emitter.extend(own);
+        stream_children(child, &own_pointer, depth + 1, params, emitter);
+    }
+    emitter.extend(search_window(&window, pointer, is_array, depth + 1, params));
+}
+
+fn search_window<'a, N: JsonNode<'a>>(window: &[JsonChild<'a, N>], pointer: &str, is_array: bool, depth: usize, params: &SearchParams) -> Vec<SearchResult> {
+    window
+        .par_chunks(PARALLEL_CHUNK)
+        .map(|chunk| {
+            let mut out = Vec::new();
+            for (key, child) in chunk {
+                search_child(pointer, key, *child, is_array, depth, params, &mut out);
+            }
+            out
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+// Search `value` and everything below it; `current_pointer` is its absolute pointer
+pub fn search_recursive<'a>(value: impl JsonNode<'a>, current_pointer: &str, params: &SearchParams, results: &mut Vec<SearchResult>) {
+    check_path(value, current_pointer, params, results);
+    search_children(value, current_pointer, 0, params, results);
+}
+
+// `depth` is the depth of `value` below the search root
+fn search_children<'a, N: JsonNode<'a>>(value: N, current_pointer: &str, depth: usize, params: &SearchParams, results: &mut Vec<SearchResult>) {
+    if params.cancelled() || !params.within_depth(depth + 1) {
+        return;
+    }
+    let is_array = value.is_array();
+    if value.child_count() >= PARALLEL_THRESHOLD {
+        let children: Vec<_> = value.children().collect();
+        results.extend(search_window(&children, current_pointer, is_array, depth + 1, params));
+        return;
+    }
+    for (key, child) in value.children() {
+        search_child(current_pointer, &key, child, is_array, depth + 1, params, results);
+    }
+}
+
+// Match a child (at `depth` below the search root) against the query, then descend into it
+fn search_child<'a>(parent_pointer: &str, key: &str, child: impl JsonNode<'a>, is_array: bool, depth: usize, params: &SearchParams, results: &mut Vec<SearchResult>) {
+    let is_container = child.is_container();
+    // Only containers and array elements need their own pointer (path check / descent)
+    let pointer = (is_container || is_array).then(|| child_pointer(parent_pointer, key));
+    check_child(parent_pointer, key, pointer.as_deref(), child, is_array, params, results);
+    if let (true, Some(pointer)) = (is_container, &pointer) {
+        search_children(child, pointer, depth, params, results);
+    }
+}
+
+// Checks for a single child, without descending: object members are key checked, containers
+// and array elements (those given a `pointer`) are path checked, primitive values are value checked.
+fn check_child<'a>(parent_pointer: &str, key: &str, pointer: Option<&str>, child: impl JsonNode<'a>, is_array: bool, params: &SearchParams, results: &mut Vec<SearchResult>) {
+    let context = || if is_array { format!("in index: {}", key) } else { format!("in key: {}", key) };
+    if let Some(filter) = params.filter {
+        if filter.matches(key, child) {
+            results.push(filter_match(parent_pointer, key, child, context()));
+        }
+        return;
+    }
+
+    // Search in keys if enabled
+    if params.search_keys && !is_array && params.matches(key) {
+        let (ranges, snippet) = params.highlight(key);
+        results.push(SearchResult {
+            node: to_node_with_truncation(parent_pointer, Some(key), child, None),
+            match_type: "key".to_string(),
+            match_text: key.to_string(),
+            context: None,
+            ranges,
+            snippet,
+        });
+    }
+
+    if let Some(pointer) = pointer {
+        check_path(child, pointer, params, results);
+    }
+
+    // Search in values if it's a primitive value
+    if params.search_values {
+        let Some(value_text) = child.scalar_text() else { return; };
+        if params.matches(&value_text) {
+            let (ranges, snippet) = params.highlight(&value_text);
             results.push(SearchResult {
-                node,
-                match_type: "path".to_string(),
-                match_text: current_pointer.to_string(),
-                context: None,
+                node: to_node_with_truncation(parent_pointer, Some(key), child, None),
+                match_type: "value".to_string(),
+                match_text: value_text.into_owned(),
+                context: Some(context()),
+                ranges,
+                snippet,
             });
         }
     }
+}
+
+fn check_path<'a>(value: impl JsonNode<'a>, pointer: &str, params: &SearchParams, results: &mut Vec<SearchResult>) {
+    if params.filter.is_some() || !params.search_paths {
+        return;
+    }
+    if params.matches(pointer) {
+        let (ranges, snippet) = params.highlight(pointer);
+        results.push(SearchResult {
+            node: create_node_for_path(value, pointer),
+            match_type: "path".to_string(),
+            match_text: pointer.to_string(),
+            context: None,
+            ranges,
+            snippet,
+        });
+    }
+}
+
+fn filter_match<'a>(parent_pointer: &str, key: &str, value: impl JsonNode<'a>, context: String) -> SearchResult {
+    let node = to_node_with_truncation(parent_pointer, Some(key), value, Some(120));
+    SearchResult {
+        match_text: node.preview.clone(),
+        node,
+        match_type: "filter".to_string(),
+        context: Some(context),
+        ranges: vec![], // structured filters match whole values, not text spans
+        snippet: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::time::Instant;
+
+    // Sequential reference walk: every child checked and descended into in document order, with
+    // none of the chunking or threading of search_scope
+    fn sequential_search<'a>(value: impl JsonNode<'a>, pointer: &str, params: &SearchParams, results: &mut Vec<SearchResult>) {
+        check_path(value, pointer, params, results);
+        sequential_children(value, pointer, params, results);
+    }
 
-    match value {
-        Value::Object(map) => {
-            for (key, val) in map.iter() {
-                let new_pointer = if current_pointer.is_empty() {
-                    format!("/{}", escape_pointer_token(key))
-                } else {
-                    format!("{}/{}", current_pointer, escape_pointer_token(key))
-                };
-
-                // Search in keys if enabled
-                if search_keys {
-                    let key_to_check = if case_sensitive { 
-                        key.to_string() 
-                    } else { 
-                        key.to_lowercase() 
-                    };
-                    let matches = text_matches(&key_to_check, query, re, whole_word);
-                    if matches {
-                        let node = to_node_with_truncation(current_pointer, Some(key), val, None);
-                        results.push(SearchResult {
-                            node,
-                            match_type: "key".to_string(),
-                            match_text: key.clone(),
-                            context: None,
-                        });
-                    }
-                }
-
-                // Search in values if it's a primitive value
-                if search_values {
-                    match val {
-                        Value::String(s) => {
-                            let value_to_check = if case_sensitive { 
-                                s.clone() 
-                            } else { 
-                                s.to_lowercase() 
-                            };
-                            let matches = text_matches(&value_to_check, query, re, whole_word);
-                            if matches {
-                                let node = to_node_with_truncation(current_pointer, Some(key), val, None);
-                                results.push(SearchResult {
-                                    node,
-                                    match_type: "value".to_string(),
-                                    match_text: s.clone(),
-                                    context: Some(format!("in key: {}", key)),
-                                });
-                            }
-                        }
-                        Value::Number(n) => {
-                            let num_str = n.to_string();
-                            let value_to_check = if case_sensitive { 
-                                num_str.clone() 
-                            } else { 
-                                num_str.to_lowercase() 
-                            };
-                            let matches = text_matches(&value_to_check, query, re, whole_word);
-                            if matches {
-                                let node = to_node_with_truncation(current_pointer, Some(key), val, None);
-                                results.push(SearchResult {
-                                    node,
-                                    match_type: "value".to_string(),
-                                    match_text: num_str,
-                                    context: Some(format!("in key: {}", key)),
-                                });
-                            }
-                        }
-                        Value::Bool(b) => {
-                            let bool_str = b.to_string();
-                            let value_to_check = if case_sensitive { 
-                                bool_str.clone() 
-                            } else { 
-                                bool_str.to_lowercase() 
-                            };
-                            let matches = text_matches(&value_to_check, query, re, whole_word);
-                            if matches {
-                                let node = to_node_with_truncation(current_pointer, Some(key), val, None);
-                                results.push(SearchResult {
-                                    node,
-                                    match_type: "value".to_string(),
-                                    match_text: bool_str,
-                                    context: Some(format!("in key: {}", key)),
-                                });
-                            }
-                        }
-                        _ => {
-                            // For objects and arrays, recurse into them
-                            search_recursive(val, &new_pointer, query, re, search_keys, search_values, search_paths, case_sensitive, whole_word, results);
-                        }
-                    }
-                } else {
-                    // If not searching values, still recurse into nested structures
-                    match val {
-                        Value::Object(_) | Value::Array(_) => {
-                            search_recursive(val, &new_pointer, query, re, search_keys, search_values, search_paths, case_sensitive, whole_word, results);
-                        }
-                        _ => {} // Don't recurse into primitives when not searching values
-                    }
-                }
+    fn sequential_children<'a>(value: impl JsonNode<'a>, pointer: &str, params: &SearchParams, results: &mut Vec<SearchResult>) {
+        let is_array = value.is_array();
+        for (key, child) in value.children() {
+            let own_pointer = child_pointer(pointer, &key);
+            let is_container = child.is_container();
+            check_child(pointer, &key, (is_container || is_array).then_some(own_pointer.as_str()), child, is_array, params, results);
+            if is_container {
+                sequential_children(child, &own_pointer, params, results);
             }
         }
-        Value::Array(arr) => {
-            for (index, item) in arr.iter().enumerate() {
-                let new_pointer = format!("{}/{}", current_pointer, index);
-                search_recursive(item, &new_pointer, query, re, search_keys, search_values, search_paths, case_sensitive, whole_word, results);
-            }
+    }
+
+    fn text_params(query: &str) -> SearchParams<'_> {
+        SearchParams {
+            query,
+            re: None,
+            search_keys: true,
+            search_values: true,
+            search_paths: true,
+            case_sensitive: false,
+            whole_word: false,
+            filter: None,
+            token: None,
+            max_depth: None,
+        }
+    }
+
+    // Large enough at the top level and inside each record to take the parallel path
+    fn sample(records: usize) -> Value {
+        let items: Vec<Value> = (0..records)
+            .map(|i| json!({
+                "id": i,
+                "name": format!("User {i}"),
+                "tags": ["alpha", "beta", format!("tag{}", i % 7)],
+                "profile": { "score": i % 100, "note": "lorem ipsum dolor sit amet", "active": i % 2 == 0 },
+                "history": (0..if i % 500 == 0 { PARALLEL_THRESHOLD + 10 } else { 3 }).map(|j| json!({ "step": j, "user": i })).collect::<Vec<_>>(),
+            }))
+            .collect();
+        json!({ "users": items })
+    }
+
+    fn run(doc: &Value, query: &str, parallel: bool) -> (Vec<Value>, std::time::Duration) {
+        let params = text_params(query);
+        let mut results = Vec::new();
+        let start = Instant::now();
+        if parallel {
+            search_scope(doc, "", &params, &mut results);
+        } else {
+            sequential_search(doc, "", &params, &mut results);
         }
-        // Primitives are handled inside object/array iteration for values
-        _ => {}
+        let elapsed = start.elapsed();
+        let results = results.iter().map(|r| serde_json::to_value(r).unwrap()).collect();
+        (results, elapsed)
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn parallel_search_matches_sequential_order() {
+        let doc = sample(3000);
+        for query in ["user 1", "alpha", "/history/5", "true"] {
+            let (sequential, _) = run(&doc, query, false);
+            let (parallel, _) = run(&doc, query, true);
+            assert!(!sequential.is_empty(), "no results for {query}");
+            assert_eq!(sequential, parallel, "results differ for {query}");
+        }
+    }
+
+    // cargo test --release search::tests::bench_parallel_search -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_parallel_search() {
+        let doc = sample(200_000);
+        for query in ["user 12", "tag3", "zzz"] {
+            let (sequential, sequential_time) = run(&doc, query, false);
+            let (parallel, parallel_time) = run(&doc, query, true);
+            assert_eq!(sequential, parallel, "results differ for {query}");
+            println!(
+                "{query:?}: {} results, sequential {sequential_time:?}, parallel {parallel_time:?} ({} threads)",
+                parallel.len(),
+                rayon::current_num_threads()
+            );
+        }
+    }
+}
@@ -1,11 +1,28 @@
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock, RwLockWriteGuard};
 use serde_json::Value;
 use std::sync::{Arc, atomic::{AtomicBool, AtomicU64}};
+use crate::types::DocumentInfo;
+use crate::history::History;
+use crate::diff::Diff;
+use crate::schema::SchemaValidation;
+use crate::lazy::LazyDocument;
+use crate::tape::TapeDocument;
+use crate::document::JsonNode;
 
 pub struct AppState {
   pub doc: RwLock<Option<Arc<Value>>>,
     pub cancel_parse: Arc<AtomicBool>,
-    pub active_search_id: AtomicU64,
+    pub active_search_id: Arc<AtomicU64>,
+    pub cancelled_search_id: Arc<AtomicU64>, // highest search id cancelled via cancel_search
+    pub doc_info: RwLock<DocumentInfo>,
+    pub dirty: AtomicBool,            // unsaved edits since last open/save
+    pub history: Mutex<History>,      // undo/redo journal for the loaded document
+    pub result_doc: RwLock<Option<Arc<Value>>>, // read-only output of the last jq transform
+    pub compare_doc: RwLock<Option<Arc<Value>>>, // second document loaded for diffing
+    pub diff: RwLock<Option<Arc<Diff>>>,         // last computed diff; cleared when the main document changes
+    pub schema_validation: RwLock<Option<Arc<SchemaValidation>>>, // last schema validation; cleared likewise
+    pub lazy_doc: RwLock<Option<Arc<LazyDocument>>>, // memory-mapped main document; `doc` is None while set
+    pub tape_doc: RwLock<Option<Arc<TapeDocument>>>, // compact main document; likewise replaces `doc`
 }
 
 impl Default for AppState {
@@ -13,7 +30,104 @@ impl Default for AppState {
         Self {
             doc: RwLock::new(None),
             cancel_parse: Arc::new(AtomicBool::new(false)),
-            active_search_id: AtomicU64::new(0),
+            active_search_id: Arc::new(AtomicU64::new(0)),
+            cancelled_search_id: Arc::new(AtomicU64::new(0)),
+            doc_info: RwLock::new(DocumentInfo::default()),
+            dirty: AtomicBool::new(false),
+            history: Mutex::new(History::default()),
+            result_doc: RwLock::new(None),
+            compare_doc: RwLock::new(None),
+            diff: RwLock::new(None),
+            schema_validation: RwLock::new(None),
+            lazy_doc: RwLock::new(None),
+            tape_doc: RwLock::new(None),
         }
     }
-}
\ No newline at end of file
+}
+
+// The main document in whichever in-memory representation it was loaded as
+pub enum MainDocument {
+    Tree(Arc<Value>),
+    Compact(Arc<TapeDocument>),
+}
+
+impl MainDocument {
+    pub fn has_pointer(&self, pointer: &str) -> bool {
+        match self {
+            MainDocument::Tree(doc) => doc.pointer(pointer).is_some(),
+            MainDocument::Compact(doc) => doc.root().pointer(pointer).is_some(),
+        }
+    }
+
+    // The document as a Value tree, for code that can't read through JsonNode (schema
+    // validation, patch diffs). A compact document is copied; the loaded one stays compact.
+    pub fn to_value(&self) -> Arc<Value> {
+        match self {
+            MainDocument::Tree(doc) => doc.clone(),
+            MainDocument::Compact(doc) => Arc::new(doc.root().to_value()),
+        }
+    }
+
+    // Whether this is still the loaded main document, i.e. no edit or reload happened since
+    pub fn is_current(&self, state: &AppState) -> bool {
+        match self {
+            MainDocument::Tree(doc) => state.doc.read().as_ref().is_some_and(|d| Arc::ptr_eq(d, doc)),
+            MainDocument::Compact(doc) => state.tape_doc.read().as_ref().is_some_and(|d| Arc::ptr_eq(d, doc)),
+        }
+    }
+}
+
+impl AppState {
+    // Resolve a browsable document by name: "main" (default) is the loaded file,
+    // "result" the output of the last jq transform, "compare" the second document for diffing.
+    // A compact main document is converted to a Value tree first (see doc_mut).
+    pub fn document(&self, source: Option<&str>) -> Result<Arc<Value>, String> {
+        let slot = match source.unwrap_or("main") {
+            "main" => {
+                if self.tape_doc.read().is_some() {
+                    drop(self.doc_mut());
+                }
+                &self.doc
+            }
+            "result" => &self.result_doc,
+            "compare" => &self.compare_doc,
+            other => return Err(format!("Unknown document: {other}")),
+        };
+        slot.read().clone().ok_or_else(|| self.missing_document())
+    }
+
+    // The main document for browsing, searching and reading values, without converting it
+    pub fn main_document(&self) -> Result<MainDocument, String> {
+        if let Some(tape) = self.tape_doc.read().clone() {
+            return Ok(MainDocument::Compact(tape));
+        }
+        self.doc.read().clone().map(MainDocument::Tree).ok_or_else(|| self.missing_document())
+    }
+
+    // Write access to the main document as a Value tree. Edits, transforms, diff and saving work
+    // on Value, so a compact document is converted on first use and stays converted.
+    pub fn doc_mut(&self) -> RwLockWriteGuard<'_, Option<Arc<Value>>> {
+        let mut guard = self.doc.write();
+        if let Some(tape) = self.tape_doc.write().take() {
+            *guard = Some(Arc::new(tape.root().to_value()));
+            self.doc_info.write().compact = false;
+        }
+        guard
+    }
+
+    // Error for commands that need the parsed main document. A memory-mapped document only
+    // supports browsing and reading values, so say that rather than "No document loaded".
+    pub fn missing_document(&self) -> String {
+        if self.lazy_doc.read().is_some() {
+            "Not available for memory-mapped documents; reopen the file with lazy loading off".into()
+        } else {
+            "No document loaded".into()
+        }
+    }
+
+    // Drop results computed from the main document (diff, schema validation) after it changes
+    pub fn invalidate_derived(&self) {
+        *self.diff.write() = None;
+        *self.schema_validation.write() = None;
+    }
+}
@@ -0,0 +1,492 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Read;
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use serde_json::{Map, Number, Value};
+use crate::document::{parse_index, JsonChild, JsonKind, JsonNode};
+
+const NO_KEY: u32 = u32::MAX;
+// With arbitrary_precision, serde_json hands each number to the visitor as a one-entry map under
+// this key, holding the number's text
+const NUMBER_TOKEN: &str = "$serde_json::private::Number";
+const NUMBER_KEY: u32 = u32::MAX - 1;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Tag {
+    Null,
+    Bool,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+// One value on the tape. Containers are followed directly by their children's entries, so a
+// whole subtree is one contiguous run and skipping it is a single addition.
+#[derive(Clone, Copy)]
+struct Entry {
+    data: u64, // bool, string or number text offset, or a container's subtree size in entries
+    key: u32,  // interned key when this is an object member, NO_KEY otherwise
+    len: u32,  // string or number text length in bytes, or a container's child count
+    tag: Tag,
+}
+
+// Compact read-only document: every value is a 24-byte entry in one vector, string values and
+// number literals share one buffer and object keys are interned, so there is no per-node
+// allocation. Object members keep document order with duplicate keys collapsed (first position,
+// last value), the same view a parsed Value gives.
+pub struct TapeDocument {
+    entries: Vec<Entry>,
+    keys: Vec<Box<str>>,
+    strings: String,
+}
+
+impl TapeDocument {
+    // Parse strict JSON straight onto the tape, without building a Value first
+    pub fn from_reader<R: Read>(reader: R) -> Result<TapeDocument, serde_json::Error> {
+        let mut de = serde_json::Deserializer::from_reader(reader);
+        let mut builder = Builder::default();
+        ValueSeed { b: &mut builder, key: NO_KEY }.deserialize(&mut de)?;
+        de.end()?;
+        let Builder { mut entries, mut keys, mut strings, .. } = builder;
+        entries.shrink_to_fit();
+        keys.shrink_to_fit();
+        strings.shrink_to_fit();
+        Ok(TapeDocument { entries, keys, strings })
+    }
+
+    pub fn root(&self) -> TapeRef<'_> {
+        TapeRef { doc: self, idx: 0 }
+    }
+
+    // Bytes held on the heap by the tape, keys and string buffer
+    pub fn heap_bytes(&self) -> usize {
+        self.entries.capacity() * size_of::<Entry>()
+            + self.keys.capacity() * size_of::<Box<str>>()
+            + self.keys.iter().map(|k| k.len()).sum::<usize>()
+            + self.strings.capacity()
+    }
+}
+
+#[derive(Default)]
+struct Builder {
+    entries: Vec<Entry>,
+    keys: Vec<Box<str>>,
+    key_ids: HashMap<Box<str>, u32>,
+    strings: String,
+}
+
+impl Builder {
+    fn intern(&mut self, key: &str) -> u32 {
+        if let Some(&id) = self.key_ids.get(key) {
+            return id;
+        }
+        let id = self.keys.len() as u32;
+        self.keys.push(key.into());
+        self.key_ids.insert(key.into(), id);
+        id
+    }
+
+    fn push(&mut self, tag: Tag, key: u32, len: u32, data: u64) {
+        self.entries.push(Entry { data, key, len, tag });
+    }
+
+    // String value, or a number's literal text
+    fn push_text<E: de::Error>(&mut self, tag: Tag, key: u32, s: &str) -> Result<(), E> {
+        let len = u32::try_from(s.len()).map_err(|_| E::custom("string too long for compact mode"))?;
+        self.push(tag, key, len, self.strings.len() as u64);
+        self.strings.push_str(s);
+        Ok(())
+    }
+
+    // Fill in the header of the container opened at `start` now that its children are on the tape
+    fn close<E: de::Error>(&mut self, start: usize, count: usize) -> Result<(), E> {
+        let len = u32::try_from(count).map_err(|_| E::custom("container too large for compact mode"))?;
+        let size = self.entries.len() - start;
+        let header = &mut self.entries[start];
+        header.len = len;
+        header.data = size as u64;
+        Ok(())
+    }
+
+    // Objects are the innermost open container while being built, so their members are the tail
+    // of the tape. Collapse duplicate keys the way serde_json's Map does: the key keeps its first
+    // position and takes the last value.
+    fn close_object<E: de::Error>(&mut self, start: usize) -> Result<(), E> {
+        let mut members = Vec::new();
+        let mut idx = start + 1;
+        while idx < self.entries.len() {
+            let end = idx + span(&self.entries[idx]);
+            members.push((self.entries[idx].key, idx, end));
+            idx = end;
+        }
+        let mut ids: Vec<u32> = members.iter().map(|m| m.0).collect();
+        ids.sort_unstable();
+        if ids.windows(2).any(|w| w[0] == w[1]) {
+            let mut last: HashMap<u32, (usize, usize)> = members.iter().map(|&(key, from, to)| (key, (from, to))).collect();
+            let kept: Vec<(usize, usize)> = members.iter().filter_map(|(key, _, _)| last.remove(key)).collect();
+            let tail = self.entries.split_off(start + 1);
+            for &(from, to) in &kept {
+                self.entries.extend_from_slice(&tail[from - start - 1..to - start - 1]);
+            }
+            return self.close(start, kept.len());
+        }
+        self.close(start, members.len())
+    }
+}
+
+// Entries covered by the value at this entry, itself included
+fn span(entry: &Entry) -> usize {
+    match entry.tag {
+        Tag::Array | Tag::Object => entry.data as usize,
+        _ => 1,
+    }
+}
+
+struct ValueSeed<'b> {
+    b: &'b mut Builder,
+    key: u32,
+}
+
+impl<'de> DeserializeSeed<'de> for ValueSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ValueSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<(), E> {
+        self.b.push(Tag::Bool, self.key, 0, v as u64);
+        Ok(())
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<(), E> {
+        self.b.push_text(Tag::Number, self.key, &v.to_string())
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<(), E> {
+        self.b.push_text(Tag::Number, self.key, &v.to_string())
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<(), E> {
+        // Same as Value: non-finite floats become null
+        match Number::from_f64(v) {
+            Some(n) => self.b.push_text(Tag::Number, self.key, &n.to_string()),
+            None => {
+                self.b.push(Tag::Null, self.key, 0, 0);
+                Ok(())
+            }
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<(), E> {
+        self.b.push_text(Tag::String, self.key, v)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<(), E> {
+        self.b.push(Tag::Null, self.key, 0, 0);
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let b = self.b;
+        let start = b.entries.len();
+        b.push(Tag::Array, self.key, 0, 0);
+        let mut count = 0;
+        while seq.next_element_seed(ValueSeed { b: &mut *b, key: NO_KEY })?.is_some() {
+            count += 1;
+        }
+        b.close(start, count)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let b = self.b;
+        let start = b.entries.len();
+        b.push(Tag::Object, self.key, 0, 0);
+        while let Some(key) = map.next_key_seed(KeySeed { b: &mut *b })? {
+            if key == NUMBER_KEY && b.entries.len() == start + 1 {
+                b.entries.truncate(start);
+                let text: String = map.next_value()?;
+                return b.push_text(Tag::Number, self.key, &text);
+            }
+            map.next_value_seed(ValueSeed { b: &mut *b, key })?;
+        }
+        b.close_object(start)
+    }
+}
+
+// Interns an object key without allocating when it has been seen before
+struct KeySeed<'b> {
+    b: &'b mut Builder,
+}
+
+impl<'de> DeserializeSeed<'de> for KeySeed<'_> {
+    type Value = u32;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<u32, D::Error> {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de> Visitor<'de> for KeySeed<'_> {
+    type Value = u32;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an object key")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<u32, E> {
+        if v == NUMBER_TOKEN {
+            return Ok(NUMBER_KEY);
+        }
+        Ok(self.b.intern(v))
+    }
+}
+
+// A value inside a TapeDocument
+#[derive(Clone, Copy)]
+pub struct TapeRef<'a> {
+    doc: &'a TapeDocument,
+    idx: usize,
+}
+
+impl<'a> TapeRef<'a> {
+    fn entry(self) -> &'a Entry {
+        &self.doc.entries[self.idx]
+    }
+
+    fn key(self) -> &'a str {
+        &self.doc.keys[self.entry().key as usize]
+    }
+
+    // Text of a string value or number literal
+    fn text(self) -> &'a str {
+        let e = self.entry();
+        let start = e.data as usize;
+        &self.doc.strings[start..start + e.len as usize]
+    }
+}
+
+// Direct children of a container: each starts where the previous one's subtree ends
+struct Children<'a> {
+    doc: &'a TapeDocument,
+    next: usize,
+    index: usize,
+    count: usize,
+    is_array: bool,
+}
+
+impl<'a> Iterator for Children<'a> {
+    type Item = JsonChild<'a, TapeRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index == self.count {
+            return None;
+        }
+        let child = TapeRef { doc: self.doc, idx: self.next };
+        let token = if self.is_array { Cow::Owned(self.index.to_string()) } else { Cow::Borrowed(child.key()) };
+        self.next += span(child.entry());
+        self.index += 1;
+        Some((token, child))
+    }
+}
+
+impl<'a> JsonNode<'a> for TapeRef<'a> {
+    fn kind(self) -> JsonKind<'a> {
+        let e = self.entry();
+        match e.tag {
+            Tag::Null => JsonKind::Null,
+            Tag::Bool => JsonKind::Bool(e.data != 0),
+            // The text was validated by the parser
+            Tag::Number => JsonKind::Number(Cow::Owned(self.text().parse().unwrap_or_else(|_| Number::from(0)))),
+            Tag::String => JsonKind::String(self.text()),
+            Tag::Array => JsonKind::Array(e.len as usize),
+            Tag::Object => JsonKind::Object(e.len as usize),
+        }
+    }
+
+    fn children(self) -> Box<dyn Iterator<Item = JsonChild<'a, Self>> + 'a> {
+        let e = self.entry();
+        let count = if matches!(e.tag, Tag::Array | Tag::Object) { e.len as usize } else { 0 };
+        Box::new(Children { doc: self.doc, next: self.idx + 1, index: 0, count, is_array: e.tag == Tag::Array })
+    }
+
+    fn get(self, token: &str) -> Option<Self> {
+        match self.entry().tag {
+            Tag::Object => self.children().find(|(k, _)| k == token).map(|(_, child)| child),
+            Tag::Array => {
+                let index = parse_index(token)?;
+                self.children().nth(index).map(|(_, child)| child)
+            }
+            _ => None,
+        }
+    }
+
+    fn scalar_text(self) -> Option<Cow<'a, str>> {
+        match self.entry().tag {
+            Tag::Number | Tag::String => Some(Cow::Borrowed(self.text())),
+            Tag::Bool => Some(Cow::Owned((self.entry().data != 0).to_string())),
+            _ => None,
+        }
+    }
+
+    fn to_value(self) -> Value {
+        match self.kind() {
+            JsonKind::Null => Value::Null,
+            JsonKind::Bool(b) => Value::Bool(b),
+            JsonKind::Number(n) => Value::Number(n.into_owned()),
+            JsonKind::String(s) => Value::String(s.to_string()),
+            JsonKind::Array(_) => Value::Array(self.children().map(|(_, c)| c.to_value()).collect()),
+            JsonKind::Object(_) => Value::Object(self.children().map(|(k, c)| (k.into_owned(), c.to_value())).collect::<Map<_, _>>()),
+        }
+    }
+}
+
+// Serializes exactly like the equivalent Value, without materializing one
+impl Serialize for TapeRef<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.kind() {
+            JsonKind::Null => serializer.serialize_unit(),
+            JsonKind::Bool(b) => serializer.serialize_bool(b),
+            JsonKind::Number(n) => n.serialize(serializer),
+            JsonKind::String(s) => serializer.serialize_str(s),
+            JsonKind::Array(len) => {
+                let mut seq = serializer.serialize_seq(Some(len))?;
+                for (_, child) in self.children() {
+                    seq.serialize_element(&child)?;
+                }
+                seq.end()
+            }
+            JsonKind::Object(len) => {
+                let mut map = serializer.serialize_map(Some(len))?;
+                for (key, child) in self.children() {
+                    map.serialize_entry(&key, &child)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+    use crate::search::{search_recursive, SearchParams};
+    use crate::types::SearchResult;
+
+    // Counts bytes allocated and not yet freed, per thread so tests running in parallel don't
+    // see each other's allocations
+    struct CountingAllocator;
+
+    thread_local! {
+        static LIVE_BYTES: Cell<isize> = const { Cell::new(0) };
+    }
+
+    fn track(delta: isize) {
+        let _ = LIVE_BYTES.try_with(|live| live.set(live.get() + delta));
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            track(layout.size() as isize);
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            track(-(layout.size() as isize));
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            track(new_size as isize - layout.size() as isize);
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    // Run `build` and return its result with the heap bytes it still holds afterwards
+    fn measure<T>(build: impl FnOnce() -> T) -> (T, usize) {
+        let before = LIVE_BYTES.with(Cell::get);
+        let result = build();
+        (result, (LIVE_BYTES.with(Cell::get) - before) as usize)
+    }
+
+    // Array of records with every kind of value, exact-precision numbers and a repeated key
+    fn records(count: usize) -> String {
+        let items: Vec<String> = (0..count)
+            .map(|i| format!(
+                r#"{{"id": {i}, "uuid": "{i:08x}-7d3c-4f1e-9a2b-{i:012x}", "name": "User {i}", "active": {}, "score": {}.{:02}, "big": 1234567890123456789{}, "tags": ["alpha", "beta", "tag{}"], "address": {{"city": "City {}", "zip": "{:05}", "geo": [{}.5, -{}.25]}}, "note": null, "name": "Renamed {i}"}}"#,
+                i % 2 == 0, i % 100, i % 97, i % 10, i % 7, i % 50, i % 100000, i % 90, i % 180
+            ))
+            .collect();
+        format!("[\n{}\n]\n", items.join(",\n"))
+    }
+
+    // One wide object of long strings
+    fn wide(count: usize) -> String {
+        let members: Vec<String> = (0..count)
+            .map(|i| format!(r#""key_{i}": "{}{i}""#, "lorem ipsum dolor sit amet ".repeat(4)))
+            .collect();
+        format!("{{{}}}", members.join(", "))
+    }
+
+    fn search(run: impl Fn(&SearchParams, &mut Vec<SearchResult>), query: &str) -> Vec<Value> {
+        let params = SearchParams {
+            query,
+            re: None,
+            search_keys: true,
+            search_values: true,
+            search_paths: true,
+            case_sensitive: false,
+            whole_word: false,
+            filter: None,
+            token: None,
+            max_depth: None,
+        };
+        let mut results = Vec::new();
+        run(&params, &mut results);
+        results.iter().map(|r| serde_json::to_value(r).unwrap()).collect()
+    }
+
+    fn compare(name: &str, text: &str, queries: &[&str]) {
+        let (value, tree_bytes) = measure(|| serde_json::from_str::<Value>(text).unwrap());
+        let (tape, tape_bytes) = measure(|| TapeDocument::from_reader(text.as_bytes()).unwrap());
+
+        // Same values, same member order, same number text
+        assert_eq!(tape.root().to_value(), value);
+        assert_eq!(serde_json::to_string(&tape.root()).unwrap(), serde_json::to_string(&value).unwrap());
+
+        for query in queries {
+            let from_value = search(|p, r| search_recursive(&value, "", p, r), query);
+            let from_tape = search(|p, r| search_recursive(tape.root(), "", p, r), query);
+            assert!(!from_value.is_empty(), "{name}: no results for {query}");
+            assert_eq!(from_value, from_tape, "{name}: results differ for {query}");
+        }
+
+        assert!(tape_bytes < tree_bytes, "{name}: tape {tape_bytes} >= tree {tree_bytes} heap bytes");
+    }
+
+    #[test]
+    fn records_match_value_and_use_less_memory() {
+        compare("records", &records(10_000), &["renamed 12", "tag3", "/address/geo", "1.05", "true"]);
+    }
+
+    #[test]
+    fn wide_object_matches_value_and_uses_less_memory() {
+        compare("wide", &wide(10_000), &["key_1999", "amet 7"]);
+    }
+}
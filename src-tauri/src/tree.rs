@@ -1,5 +1,7 @@
+use std::collections::HashMap;
 use serde_json::Value;
-use crate::types::Node;
+use crate::types::{Node, MatchRange, MatchSnippet};
+use crate::document::{JsonKind, JsonNode};
 
 pub fn truncate(s: &str, max: usize) -> String {
     if s.len() <= max { 
@@ -9,43 +11,39 @@ pub fn truncate(s: &str, max: usize) -> String {
     }
 }
 
+pub fn value_type_name<'a>(v: impl JsonNode<'a>) -> &'static str {
+    v.type_name()
+}
+
 // JSON Pointer token escape (~0, ~1)
 pub fn escape_pointer_token(raw: &str) -> String {
     raw.replace('~', "~0").replace('/', "~1")
 }
 
-pub fn to_node_with_truncation(parent_ptr: &str, key: Option<&str>, v: &Value, truncate_limit: Option<usize>) -> Node {
-    let (value_type, has_children, child_count, preview) = match v {
-        Value::Object(m) => (
-            "object".into(),
-            !m.is_empty(),
-            m.len(),
-            if m.is_empty() { 
-                format!("{{}} {} keys", m.len()) 
-            } else { 
-                format!("{{…}} {} keys", m.len()) 
-            }
-        ),
-        Value::Array(a) => (
-            "array".into(), 
-            !a.is_empty(), 
-            a.len(), 
-            if a.is_empty() { 
-                format!("[] {} items", a.len()) 
-            } else { 
-                format!("[…] {} items", a.len()) 
-            }
-        ),
-        Value::String(s) => ("string".into(), false, 0, 
+// Preview text for an object (`is_object`) or array with `count` children
+pub fn container_preview(is_object: bool, count: usize) -> String {
+    match (is_object, count) {
+        (true, 0) => format!("{{}} {} keys", count),
+        (true, _) => format!("{{…}} {} keys", count),
+        (false, 0) => format!("[] {} items", count),
+        (false, _) => format!("[…] {} items", count),
+    }
+}
+
+pub fn to_node_with_truncation<'a>(parent_ptr: &str, key: Option<&str>, v: impl JsonNode<'a>, truncate_limit: Option<usize>) -> Node {
+    let (has_children, child_count, preview) = match v.kind() {
+        JsonKind::Object(n) => (n > 0, n, container_preview(true, n)),
+        JsonKind::Array(n) => (n > 0, n, container_preview(false, n)),
+        JsonKind::String(s) => (false, 0, 
             if let Some(limit) = truncate_limit {
                 truncate(s, limit)
             } else {
                 s.to_string()
             }
         ),
-        Value::Number(n) => ("number".into(), false, 0, n.to_string()),
-        Value::Bool(b) => ("boolean".into(), false, 0, b.to_string()),
-        Value::Null => ("null".into(), false, 0, "null".into()),
+        JsonKind::Number(n) => (false, 0, n.to_string()),
+        JsonKind::Bool(b) => (false, 0, b.to_string()),
+        JsonKind::Null => (false, 0, "null".into()),
     };
     
     let pointer = if let Some(k) = key {
@@ -57,39 +55,23 @@ pub fn to_node_with_truncation(parent_ptr: &str, key: Option<&str>, v: &Value, t
     Node { 
         pointer, 
         key: key.map(|s| s.to_string()), 
-        value_type, 
+        value_type: v.type_name().into(), 
         has_children, 
         child_count, 
-        preview 
+        preview,
+        diff: None,
+        validation: None,
     }
 }
 
-pub fn create_node_for_path(value: &Value, pointer: &str) -> Node {
-    let (value_type, has_children, child_count, preview) = match value {
-        Value::Object(m) => (
-            "object".into(),
-            !m.is_empty(),
-            m.len(),
-            if m.is_empty() { 
-                format!("{{}} {} keys", m.len()) 
-            } else { 
-                format!("{{…}} {} keys", m.len()) 
-            }
-        ),
-        Value::Array(a) => (
-            "array".into(), 
-            !a.is_empty(), 
-            a.len(), 
-            if a.is_empty() { 
-                format!("[] {} items", a.len()) 
-            } else { 
-                format!("[…] {} items", a.len()) 
-            }
-        ),
-        Value::String(s) => ("string".into(), false, 0, truncate(s, 120)),
-        Value::Number(n) => ("number".into(), false, 0, n.to_string()),
-        Value::Bool(b) => ("boolean".into(), false, 0, b.to_string()),
-        Value::Null => ("null".into(), false, 0, "null".into()),
+pub fn create_node_for_path<'a>(value: impl JsonNode<'a>, pointer: &str) -> Node {
+    let (has_children, child_count, preview) = match value.kind() {
+        JsonKind::Object(n) => (n > 0, n, container_preview(true, n)),
+        JsonKind::Array(n) => (n > 0, n, container_preview(false, n)),
+        JsonKind::String(s) => (false, 0, truncate(s, 120)),
+        JsonKind::Number(n) => (false, 0, n.to_string()),
+        JsonKind::Bool(b) => (false, 0, b.to_string()),
+        JsonKind::Null => (false, 0, "null".into()),
     };
     
     // Extract key from pointer
@@ -102,55 +84,223 @@ pub fn create_node_for_path(value: &Value, pointer: &str) -> Node {
     Node { 
         pointer: pointer.to_string(), 
         key, 
-        value_type, 
+        value_type: value.type_name().into(), 
         has_children, 
         child_count, 
-        preview 
+        preview,
+        diff: None,
+        validation: None,
     }
 }
 
-pub fn list_children(root: &Value, pointer: &str, offset: usize, limit: usize) -> Vec<Node> {
+pub fn list_children<'a>(root: impl JsonNode<'a>, pointer: &str, offset: usize, limit: usize) -> Vec<Node> {
     let target = root.pointer(pointer).unwrap_or(root);
-    match target {
-        Value::Object(map) => map
-            .iter()
-            .skip(offset)
-            .take(limit)
-            .map(|(k, v)| to_node_with_truncation(pointer, Some(k), v, None))
-            .collect(),
-        Value::Array(arr) => arr
-            .iter()
-            .enumerate()
-            .skip(offset)
-            .take(limit)
-            .map(|(i, v)| to_node_with_truncation(pointer, Some(&i.to_string()), v, None))
-            .collect(),
-        _ => vec![],
-    }
+    target
+        .children()
+        .skip(offset)
+        .take(limit)
+        .map(|(k, v)| to_node_with_truncation(pointer, Some(&k), v, None))
+        .collect()
+}
+
+// The regex search and replace both use: case-insensitive unless `case_sensitive`, and anchored
+// at word boundaries for `whole_word`. It runs on the original text, never the lowercased copy.
+pub fn search_regex(query: &str, case_sensitive: bool, whole_word: bool) -> Result<regex::Regex, regex::Error> {
+    let pattern = if whole_word { format!(r"\b(?:{query})\b") } else { query.to_string() };
+    regex::RegexBuilder::new(&pattern).case_insensitive(!case_sensitive).build()
 }
 
 pub fn text_matches(text: &str, query: &str, re: Option<&regex::Regex>, whole_word: bool) -> bool {
+    match_byte_ranges(text, query, re, whole_word).next().is_some()
+}
+
+// Byte ranges of every match of `query` in `text`; text_matches is "at least one of these".
+// Note: text and query should already be case-normalized if needed
+fn match_byte_ranges<'t>(text: &'t str, query: &'t str, re: Option<&'t regex::Regex>, whole_word: bool) -> Box<dyn Iterator<Item = (usize, usize)> + 't> {
     if let Some(re) = re {
         // If regex is enabled, use regex matching
-        re.is_match(text)
+        Box::new(re.find_iter(text).map(|m| (m.start(), m.end())))
     } else if whole_word {
-        // For whole word matching without regex, we need to check word boundaries
-        // Note: text and query should already be case-normalized if needed
-        text.split(|c: char| !c.is_alphanumeric())
-            .any(|word| word == query)
+        // For whole word matching without regex, compare whole words split at word boundaries
+        Box::new(text.split(|c: char| !c.is_alphanumeric())
+            .filter(move |word| *word == query)
+            .map(move |word| {
+                let start = word.as_ptr() as usize - text.as_ptr() as usize;
+                (start, start + word.len())
+            }))
     } else {
         // Regular substring search
-        // Note: text and query should already be case-normalized if needed
-        text.contains(query)
+        Box::new(text.match_indices(query).map(|(i, m)| (i, i + m.len())))
     }
 }
 
-// Helper to rebuild a Node for a specific pointer after mutation
-pub fn build_node_for_pointer(root: &Value, pointer: &str) -> Result<Node, String> {
-    let value = if pointer.is_empty() { 
-        root 
-    } else { 
-        root.pointer(pointer).ok_or("Invalid pointer")? 
+const MAX_MATCH_RANGES: usize = 1000;
+
+// Byte ranges of up to `limit` matches in `text` as search sees it: case-insensitive literal
+// matching runs on the lowercased text (query already lowercased), and ranges are mapped back
+// onto `text`. A regex (from search_regex) handles case itself.
+pub fn text_match_byte_ranges(text: &str, query: &str, re: Option<&regex::Regex>, whole_word: bool, case_sensitive: bool, limit: usize) -> Vec<(usize, usize)> {
+    if case_sensitive || re.is_some() {
+        return match_byte_ranges(text, query, re, whole_word).take(limit).collect();
+    }
+    // Lowercasing can change byte lengths, so record which original char each lowered byte came from
+    let lowered = text.to_lowercase();
+    let mut origin = Vec::with_capacity(lowered.len());
+    for (i, c) in text.char_indices() {
+        let width: usize = c.to_lowercase().map(char::len_utf8).sum();
+        origin.extend(std::iter::repeat_n((i, i + c.len_utf8()), width));
+    }
+    if origin.len() != lowered.len() {
+        return vec![]; // context-dependent lowercasing we can't map back
+    }
+    match_byte_ranges(&lowered, query, re, whole_word)
+        .take(limit)
+        .map(|(start, end)| {
+            let orig_start = origin.get(start).map_or(text.len(), |o| o.0);
+            let orig_end = if end > start { origin[end - 1].1 } else { orig_start };
+            (orig_start, orig_end)
+        })
+        .collect()
+}
+
+// Highlight ranges (bytes and chars) for the matches search found in `text`
+pub fn text_match_ranges(text: &str, query: &str, re: Option<&regex::Regex>, whole_word: bool, case_sensitive: bool) -> Vec<MatchRange> {
+    let byte_ranges = text_match_byte_ranges(text, query, re, whole_word, case_sensitive, MAX_MATCH_RANGES);
+
+    // Ranges come in ascending order, so char offsets can be counted incrementally
+    let (mut byte_pos, mut char_pos) = (0, 0);
+    let mut char_offset = |byte: usize| {
+        char_pos += text[byte_pos..byte].chars().count();
+        byte_pos = byte;
+        char_pos
     };
+    byte_ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let char_start = char_offset(start);
+            let char_end = char_offset(end);
+            MatchRange { start, end, char_start, char_end }
+        })
+        .collect()
+}
+
+const SNIPPET_CHARS: usize = 160;
+const SNIPPET_LEAD: usize = 40;
+
+// A window of `text` around the first match, for strings too long to show whole
+pub fn match_snippet(text: &str, ranges: &[MatchRange]) -> Option<MatchSnippet> {
+    let first = ranges.first()?;
+    let total_chars = text.chars().count();
+    if total_chars <= SNIPPET_CHARS {
+        return None;
+    }
+    let char_start = first.char_start.saturating_sub(SNIPPET_LEAD);
+    let char_end = (char_start + SNIPPET_CHARS).max(first.char_end).min(total_chars);
+    let byte_at = |chars: usize| text.char_indices().nth(chars).map_or(text.len(), |(i, _)| i);
+    let (start, end) = (byte_at(char_start), byte_at(char_end));
+    Some(MatchSnippet {
+        text: text[start..end].to_string(),
+        byte_offset: start,
+        char_offset: char_start,
+        ranges: ranges
+            .iter()
+            .filter(|r| r.start >= start && r.end <= end)
+            .map(|r| MatchRange {
+                start: r.start - start,
+                end: r.end - start,
+                char_start: r.char_start - char_start,
+                char_end: r.char_end - char_start,
+            })
+            .collect(),
+    })
+}
+
+// Helper to rebuild a Node for a specific pointer after mutation
+pub fn build_node_for_pointer<'a>(root: impl JsonNode<'a>, pointer: &str) -> Result<Node, String> {
+    let value = root.pointer(pointer).ok_or("Invalid pointer")?;
     Ok(create_node_for_path(value, pointer))
-}
\ No newline at end of file
+}
+// JSON Pointer token unescape (inverse of escape_pointer_token)
+pub fn unescape_pointer_token(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+pub fn child_pointer(parent_ptr: &str, token: &str) -> String {
+    if token.contains(['~', '/']) {
+        format!("{}/{}", parent_ptr, escape_pointer_token(token))
+    } else {
+        format!("{}/{}", parent_ptr, token)
+    }
+}
+
+// Split a pointer into its parent pointer and (unescaped) last token; None for the root
+pub fn split_pointer(pointer: &str) -> Option<(&str, String)> {
+    let idx = pointer.rfind('/')?;
+    Some((&pointer[..idx], unescape_pointer_token(&pointer[idx + 1..])))
+}
+
+pub fn value_at_mut<'a>(root: &'a mut Value, pointer: &str) -> Result<&'a mut Value, String> {
+    if pointer.is_empty() {
+        Ok(root)
+    } else {
+        root.pointer_mut(pointer).ok_or_else(|| format!("Invalid pointer: {pointer}"))
+    }
+}
+
+// Insert `value` at `pointer`, whose last token names the new object key or array index.
+// For objects `index` is the position among the parent's keys.
+pub fn insert_at_pointer(root: &mut Value, pointer: &str, index: usize, value: Value) -> Result<(), String> {
+    let (parent_ptr, token) = split_pointer(pointer).ok_or("Cannot insert at the document root")?;
+    match value_at_mut(root, parent_ptr)? {
+        Value::Object(map) => {
+            if map.contains_key(&token) {
+                return Err(format!("Key already exists: {token}"));
+            }
+            let index = index.min(map.len());
+            map.shift_insert(index, token, value);
+        }
+        Value::Array(arr) => {
+            let i: usize = token.parse().map_err(|_| format!("Invalid array index: {token}"))?;
+            if i > arr.len() {
+                return Err(format!("Array index out of bounds: {i}"));
+            }
+            arr.insert(i, value);
+        }
+        _ => return Err("Parent is not an object or array".into()),
+    }
+    Ok(())
+}
+
+// Remove the value at `pointer`, returning its former position in the parent and the value
+pub fn remove_at_pointer(root: &mut Value, pointer: &str) -> Result<(usize, Value), String> {
+    let (parent_ptr, token) = split_pointer(pointer).ok_or("Cannot remove the document root")?;
+    match value_at_mut(root, parent_ptr)? {
+        Value::Object(map) => {
+            let index = map.keys().position(|k| *k == token).ok_or("Invalid pointer")?;
+            let removed = map.shift_remove(&token).ok_or("Invalid pointer")?;
+            Ok((index, removed))
+        }
+        Value::Array(arr) => {
+            let i: usize = token.parse().map_err(|_| format!("Invalid array index: {token}"))?;
+            if i >= arr.len() {
+                return Err("Invalid pointer".into());
+            }
+            Ok((i, arr.remove(i)))
+        }
+        _ => Err("Invalid pointer".into()),
+    }
+}
+
+// Record `status` for a pointer and `ancestor_status` for every container above it, so the tree
+// can flag collapsed nodes with something interesting inside (a pointer's own status wins)
+pub fn mark_with_ancestors(statuses: &mut HashMap<String, &'static str>, pointer: &str, status: &'static str, ancestor_status: &'static str) {
+    statuses.insert(pointer.to_string(), status);
+    let mut ancestor = pointer;
+    while let Some(idx) = ancestor.rfind('/') {
+        ancestor = &ancestor[..idx];
+        if statuses.contains_key(ancestor) {
+            break; // its ancestors were marked along with it
+        }
+        statuses.insert(ancestor.to_string(), ancestor_status);
+    }
+}
@@ -1,4 +1,5 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Serialize)]
 pub struct Node {
@@ -8,6 +9,8 @@ pub struct Node {
     pub has_children: bool,
     pub child_count: usize,
     pub preview: String,          // short preview for leafs / strings / numbers
+    pub diff: Option<String>,     // vs the compare document: "added" | "removed" | "changed" | "modified" (descendants differ)
+    pub validation: Option<String>, // after schema validation: "invalid" | "contains_invalid"
 }
 
 #[derive(Serialize)]
@@ -16,6 +19,25 @@ pub struct SearchResult {
     pub match_type: String,       // "key", "value", "path"
     pub match_text: String,       // the actual matched text
     pub context: Option<String>,  // additional context if needed
+    pub ranges: Vec<MatchRange>,  // every match inside match_text
+    pub snippet: Option<MatchSnippet>, // window around the first match when match_text is long
+}
+
+// A match inside a string, as byte offsets and as char (Unicode scalar) offsets
+#[derive(Serialize, Clone, Copy)]
+pub struct MatchRange {
+    pub start: usize,
+    pub end: usize,
+    pub char_start: usize,
+    pub char_end: usize,
+}
+
+#[derive(Serialize)]
+pub struct MatchSnippet {
+    pub text: String,
+    pub byte_offset: usize,       // where `text` starts inside match_text
+    pub char_offset: usize,
+    pub ranges: Vec<MatchRange>,  // matches inside the window, relative to `text`
 }
 
 #[derive(Serialize)]
@@ -23,4 +45,213 @@ pub struct SearchResponse {
     pub results: Vec<SearchResult>,
     pub total_count: usize,
     pub has_more: bool,
-}
\ No newline at end of file
+}
+
+#[derive(Serialize, Clone)]
+pub struct LineError {
+    pub line: usize,              // 1-based line number in the source file
+    pub message: String,
+}
+
+// Error from the commands that open a document. `message` is always set and reads on its own
+// (for malformed input it includes the context block and likely cause); `parse` has the details.
+#[derive(Serialize, Debug)]
+pub struct OpenError {
+    pub message: String,
+    pub parse: Option<Box<ParseError>>,
+}
+
+impl From<String> for OpenError {
+    fn from(message: String) -> Self {
+        OpenError { message, parse: None }
+    }
+}
+
+// Where and (probably) why a document failed to parse
+#[derive(Serialize, Debug)]
+pub struct ParseError {
+    pub message: String,          // parser message without the position
+    pub line: usize,              // 1-based
+    pub column: usize,            // 1-based, in characters
+    pub offset: usize,            // byte offset from the start of the source
+    pub context: Vec<SourceLine>, // the error line and up to two lines before it
+    pub caret: usize,             // 1-based character position of the error in the last context line
+    pub cause: Option<String>,    // "truncated" | "trailing_comma" | "unquoted_key" | "single_quotes" | "comment" | ...
+    pub hint: Option<String>,     // human-readable explanation of `cause`
+}
+
+#[derive(Serialize, Debug)]
+pub struct SourceLine {
+    pub line: usize,
+    pub text: String, // long lines are cut to a window around the error, marked with "…"
+}
+
+// Metadata about the currently loaded document (source format, load-time diagnostics)
+#[derive(Serialize, Clone, Default)]
+pub struct DocumentInfo {
+    pub path: Option<String>,     // None when loaded from clipboard
+    pub format: String,           // "json" | "ndjson" | "json5"
+    pub lenient: bool,            // parsed in lenient mode; comments/trailing commas are lost on save
+    pub line_errors: Vec<LineError>, // NDJSON lines that failed to parse (capped)
+    pub line_error_count: usize,  // total failed lines, including those beyond the cap
+    pub lazy: bool,               // memory-mapped and browsed in place; read-only
+    pub compact: bool,            // held in the compact tape representation (until first edit)
+    pub repairs: Vec<Repair>,     // fixes made by a recovery parse (capped); empty for clean input
+    pub repair_count: usize,      // total repairs, including those beyond the cap
+    pub duplicate_keys: Vec<DuplicateKey>, // object keys that occur more than once (capped)
+    pub duplicate_key_count: usize, // total duplicated keys, including those beyond the cap
+}
+
+// An object key that occurs more than once in the source. The loaded document keeps the last
+// value at the first key's position; `occurrences` has every one of them.
+#[derive(Serialize, Clone)]
+pub struct DuplicateKey {
+    pub pointer: String,          // the member as the loaded document has it
+    pub key: String,
+    pub occurrences: Vec<KeyOccurrence>, // in source order; the last one is the loaded value
+}
+
+#[derive(Serialize, Clone)]
+pub struct KeyOccurrence {
+    pub offset: usize,            // byte offset of the key's opening quote
+    pub line: usize,              // 1-based
+    pub column: usize,            // 1-based, in characters
+    pub value: Value,
+}
+
+#[derive(Serialize)]
+pub struct LintReport {
+    pub path: Option<String>,     // file that was checked; None for clipboard documents
+    pub duplicate_keys: Vec<DuplicateKey>,
+}
+
+// One fix made while recovering a malformed document, so the UI can tell synthesized parts
+// from what was actually in the source
+#[derive(Serialize, Clone)]
+pub struct Repair {
+    pub action: String,           // "close_string" | "close_container" | "insert_value" | "insert_colon" | "insert_comma" | "remove_comma" | "complete_literal" | "truncate_number" | "invalid_escape" | "invalid_utf8" | "skip_invalid" | "skip_trailing"
+    pub offset: usize,            // byte offset in the source where the repair applies
+    pub length: usize,            // source bytes dropped or replaced; 0 for insertions
+    pub pointer: String,          // node the repair belongs to
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct MemoryUsage {
+    pub representation: String, // "tree" | "compact" | "lazy"
+    pub bytes: usize,           // heap held by the loaded document (estimated for "tree")
+}
+
+#[derive(Serialize)]
+pub struct HistoryStatus {
+    pub undo_count: usize,
+    pub redo_count: usize,
+    pub next_undo: Option<String>,    // label of the edit undo would revert
+    pub next_redo: Option<String>,
+    pub used_bytes: usize,            // approximate journal memory use
+    pub budget_bytes: usize,
+}
+
+// Both parents affected by move_node, with their refreshed first page of children
+#[derive(Serialize)]
+pub struct MoveResult {
+    pub source_parent: String,    // where the node was removed, as it is addressed after the move
+    pub source: Vec<Node>,
+    pub target_parent: String,
+    pub target: Vec<Node>,
+}
+
+// Outcome (or dry-run preview) of replacing a value with an arbitrary JSON literal
+#[derive(Serialize)]
+pub struct ValueChange {
+    pub pointer: String,
+    pub old_type: String,
+    pub new_type: String,
+    pub type_changed: bool,
+    pub applied: bool,            // false for dry runs
+    pub node: Node,               // the node as it is (or would be) after the change
+}
+
+#[derive(Serialize)]
+pub struct TransformResult {
+    pub output_count: usize,      // number of values the filter produced
+    pub replaced: bool,           // result replaced the loaded document
+    pub nodes: Vec<Node>,         // first page of the result's top-level children
+}
+// One find/replace hit: a key rename or a string value rewrite at `pointer`
+#[derive(Serialize)]
+pub struct Replacement {
+    pub pointer: String,          // pointer before any replacement is applied
+    pub target: String,           // "key" or "value"
+    pub before: String,
+    pub after: String,
+}
+
+// Options for replace_all, passed as one object so the command keeps a short argument list
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceOptions {
+    pub search_keys: bool,
+    pub search_values: bool,
+    pub case_sensitive: bool,
+    pub regex: bool,
+    pub whole_word: bool,
+    pub root_pointer: Option<String>, // scope; defaults to the document root
+    pub max_depth: Option<usize>,
+    pub dry_run: Option<bool>,
+    pub limit: Option<usize>,     // preview length; defaults to 1000
+}
+
+#[derive(Serialize)]
+pub struct ReplaceResult {
+    pub replacements: Vec<Replacement>, // first `limit` replacements, in document order
+    pub total_count: usize,
+    pub applied: bool,            // false for a dry-run preview
+}
+
+// One difference between the main document (left) and the compare document (right)
+#[derive(Serialize)]
+pub struct DiffEntry {
+    pub kind: String,                  // "added" | "removed" | "changed"
+    pub left_pointer: Option<String>,  // location in the main document, absent when added
+    pub right_pointer: Option<String>, // location in the compare document, absent when removed
+    pub left: Option<Node>,
+    pub right: Option<Node>,
+}
+
+#[derive(Serialize)]
+pub struct DiffResponse {
+    pub entries: Vec<DiffEntry>,
+    pub total_count: usize,
+    pub has_more: bool,
+}
+
+#[derive(Serialize)]
+pub struct DiffSummary {
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+}
+
+#[derive(Serialize)]
+pub struct PatchResult {
+    pub format: String,           // "json-patch" (RFC 6902) or "merge-patch" (RFC 7386)
+    pub operations: usize,        // patch operations processed
+    pub changes: usize,           // recorded edit ops (0 when nothing changed)
+    pub applied: bool,            // false for a dry run
+}
+
+// A schema violation at an instance location
+#[derive(Serialize)]
+pub struct SchemaError {
+    pub pointer: String,          // instance pointer of the invalid value
+    pub schema_path: String,      // pointer into the schema of the failing keyword
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct ValidationReport {
+    pub valid: bool,
+    pub error_count: usize,
+    pub errors: Vec<SchemaError>, // first MAX_SCHEMA_ERRORS errors
+}
@@ -224,7 +224,9 @@ function App() {
             const filePaths = event.payload.paths;
             if (filePaths.length > 0) {
               const jsonFile = filePaths.find((path: string) =>
-                path.toLowerCase().endsWith(".json")
+                [".json", ".ndjson", ".jsonl"].some((ext) =>
+                  path.toLowerCase().endsWith(ext)
+                )
               );
               if (jsonFile) {
                 console.log("📁 Loading JSON file via Tauri:", jsonFile);
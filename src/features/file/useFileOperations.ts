@@ -2,7 +2,13 @@ import { useCallback, useRef } from "react";
 import { listen } from "@tauri-apps/api/event";
 import { invoke } from "@tauri-apps/api/core";
 import { useFileStore } from "./fileStore";
-import type { Node } from "@shared/types";
+import type { Node, OpenError } from "@shared/types";
+
+// Open commands reject with an OpenError object; other failures are plain strings
+const describeOpenError = (error: unknown): string =>
+  typeof error === "object" && error !== null && "message" in error
+    ? (error as OpenError).message
+    : String(error);
 
 export const useFileOperations = () => {
   const {
@@ -93,7 +99,7 @@ export const useFileOperations = () => {
           return; // error from stale request; silently drop
         }
         console.error("Failed to load file:", error);
-        const errorMessage = `Failed to load file: ${error}`;
+        const errorMessage = `Failed to load file: ${describeOpenError(error)}`;
         setError(errorMessage);
         setNodes([]);
         setFileName("");
@@ -143,7 +149,7 @@ export const useFileOperations = () => {
         options?.onSuccess?.(result);
       } catch (error) {
         console.error("Failed to load clipboard JSON:", error);
-        const msg = `Failed to load clipboard JSON: ${error}`;
+        const msg = `Failed to load clipboard JSON: ${describeOpenError(error)}`;
         setError(msg);
         setNodes([]);
         setFileName("");
@@ -6,6 +6,25 @@ export interface Node {
   has_children: boolean;
   child_count: number;
   preview: string;
+  diff?: "added" | "removed" | "changed" | "modified" | null;
+  validation?: "invalid" | "contains_invalid" | null;
+}
+
+// Error returned by open_file / open_clipboard; parse is set when the input is malformed
+export interface OpenError {
+  message: string;
+  parse?: ParseError | null;
+}
+
+export interface ParseError {
+  message: string;
+  line: number;
+  column: number;
+  offset: number;
+  context: { line: number; text: string }[];
+  caret: number;
+  cause?: string | null;
+  hint?: string | null;
 }
 
 // Search-related types
@@ -14,6 +33,24 @@ export interface SearchResult {
   match_type: string;
   match_text: string;
   context?: string;
+  ranges: MatchRange[];
+  snippet?: MatchSnippet;
+}
+
+// Match position inside match_text: byte offsets plus char (code point) offsets
+export interface MatchRange {
+  start: number;
+  end: number;
+  char_start: number;
+  char_end: number;
+}
+
+// Window of a long match_text around its first match; ranges are relative to text
+export interface MatchSnippet {
+  text: string;
+  byte_offset: number;
+  char_offset: number;
+  ranges: MatchRange[];
 }
 
 export interface SearchResponse {